@@ -0,0 +1,259 @@
+//! Macros that decode PEM at compile time with the parser of `nom_pem`.
+//!
+//! Malformed input and blocks with an unexpected label fail the build with the
+//! parser's error instead of failing at runtime.
+//!
+//!```ignore
+//! #[macro_use]
+//! extern crate nom_pem_macros;
+//!
+//! // DER of the single block in the file, relative to the crate root
+//! static CA: &[u8] = include_pem!("certs/ca.pem", "CERTIFICATE");
+//! // label and DER of every block in the file
+//! static CHAIN: &[(&str, &[u8])] = include_pem_bundle!("certs/chain.pem");
+//! ```
+//!
+//! `pem!` and `pem_bundle!` do the same for a string literal.
+//!
+//! `FromPem` and `ToPem` are derived for newtypes, with the labels given as `pem` attributes,
+//! the first label is the one written by `ToPem`:
+//!
+//!```ignore
+//! #[derive(FromPem, ToPem)]
+//! #[pem(label = "CERTIFICATE", label = "X509 CERTIFICATE")]
+//! struct Certificate(Vec<u8>);
+//! ```
+
+extern crate nom_pem;
+extern crate proc_macro;
+extern crate proc_macro2;
+extern crate quote;
+extern crate syn;
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use nom_pem::decode_blocks;
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{quote, quote_spanned};
+use syn::parse::{Parse, ParseStream};
+use proc_macro2::Span;
+use syn::{parse_macro_input, DeriveInput, LitByteStr, LitStr, Token};
+
+/// a string literal with the PEM or its path, and optionally the expected label
+struct Args {
+    source: LitStr,
+    label: Option<LitStr>,
+}
+
+impl Parse for Args {
+    fn parse(input: ParseStream) -> syn::Result<Args> {
+        let source = input.parse()?;
+        let mut label = None;
+        if input.parse::<Option<Token![,]>>()?.is_some() && !input.is_empty() {
+            label = Some(input.parse()?);
+            input.parse::<Option<Token![,]>>()?;
+        }
+        Ok(Args { source, label })
+    }
+}
+
+/// decodes all blocks of `input` into their labels and data, checking them against `label`
+fn decode(input: &[u8], label: Option<&str>) -> Result<Vec<(String, Vec<u8>)>, String> {
+    // decode_blocks needs the line ending after the last END line
+    let mut input = input.to_vec();
+    if input.last() != Some(&b'\n') {
+        input.push(b'\n');
+    }
+    let blocks = decode_blocks(&input).map_err(|e| e.to_string())?;
+    let mut ret = Vec::with_capacity(blocks.len());
+    for block in &blocks {
+        if let Some(label) = label {
+            if block.block_type != label {
+                return Err(format!("expected {} block, found {}", label, block.block_type));
+            }
+        }
+        ret.push((String::from(block.block_type), block.data.clone()));
+    }
+    Ok(ret)
+}
+
+/// expands to the data of the single block in `input` as `&'static [u8]`
+fn expand_block(input: &[u8], label: Option<&str>) -> Result<TokenStream2, String> {
+    let mut blocks = decode(input, label)?;
+    if blocks.len() != 1 {
+        return Err(format!("expected one block, found {}, use a bundle macro for several", blocks.len()));
+    }
+    let data = LitByteStr::new(&blocks.remove(0).1, Span::call_site());
+    Ok(quote!(#data as &'static [u8]))
+}
+
+/// expands to the labels and data of the blocks in `input` as `&'static [(&'static str, &'static [u8])]`
+fn expand_bundle(input: &[u8], label: Option<&str>) -> Result<TokenStream2, String> {
+    let blocks = decode(input, label)?.into_iter().map(|(label, data)| {
+        let data = LitByteStr::new(&data, Span::call_site());
+        quote!((#label, #data as &'static [u8]))
+    });
+    Ok(quote!({
+        const BLOCKS: &'static [(&'static str, &'static [u8])] = &[#(#blocks),*];
+        BLOCKS
+    }))
+}
+
+/// `compile_error!` at `span`, without the `::core` path of `syn::Error`
+/// that does not resolve in 2015 edition crates
+fn compile_error(span: Span, message: String) -> TokenStream {
+    quote_spanned!(span=> compile_error! { #message }).into()
+}
+
+type Expand = fn(&[u8], Option<&str>) -> Result<TokenStream2, String>;
+
+fn expand_literal(args: Args, expand: Expand) -> TokenStream {
+    let label = args.label.as_ref().map(LitStr::value);
+    match expand(args.source.value().as_bytes(), label.as_deref()) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => compile_error(args.source.span(), format!("invalid PEM: {}", e)),
+    }
+}
+
+/// reads the file relative to the root of the calling crate, like cargo does for build scripts
+fn expand_file(args: Args, expand: Expand) -> TokenStream {
+    let name = args.source.value();
+    let path = match env::var_os("CARGO_MANIFEST_DIR") {
+        Some(dir) => PathBuf::from(dir).join(&name),
+        None => PathBuf::from(&name),
+    };
+    let label = args.label.as_ref().map(LitStr::value);
+    let result = fs::read(&path)
+        .map_err(|e| format!("cannot read {}: {}", path.display(), e))
+        .and_then(|input| expand(&input, label.as_deref()).map_err(|e| format!("invalid PEM in {}: {}", name, e)));
+    match result {
+        Ok(tokens) => {
+            // include_bytes! makes the build depend on the file
+            let path = path.to_string_lossy();
+            quote!({
+                const _: &[u8] = include_bytes!(#path);
+                #tokens
+            }).into()
+        }
+        Err(e) => compile_error(args.source.span(), e),
+    }
+}
+
+/// `include_pem!("path", "LABEL")` expands to the data of the single block in the file
+/// as `&'static [u8]`, the label is optional and the path is relative to the crate root.
+#[proc_macro]
+pub fn include_pem(input: TokenStream) -> TokenStream {
+    expand_file(parse_macro_input!(input as Args), expand_block)
+}
+
+/// `include_pem_bundle!("path", "LABEL")` expands to the labels and data of all blocks in the file
+/// as `&'static [(&'static str, &'static [u8])]`, the label is optional and applies to every block.
+#[proc_macro]
+pub fn include_pem_bundle(input: TokenStream) -> TokenStream {
+    expand_file(parse_macro_input!(input as Args), expand_bundle)
+}
+
+/// `pem!("-----BEGIN ...", "LABEL")` is `include_pem!` for a string literal.
+#[proc_macro]
+pub fn pem(input: TokenStream) -> TokenStream {
+    expand_literal(parse_macro_input!(input as Args), expand_block)
+}
+
+/// `pem_bundle!("-----BEGIN ...", "LABEL")` is `include_pem_bundle!` for a string literal.
+#[proc_macro]
+pub fn pem_bundle(input: TokenStream) -> TokenStream {
+    expand_literal(parse_macro_input!(input as Args), expand_bundle)
+}
+
+/// the labels of the `pem` attributes and the field of a newtype
+fn newtype_with_labels(input: &DeriveInput) -> syn::Result<(TokenStream2, Vec<LitStr>)> {
+    let mut labels = Vec::new();
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("pem")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("label") {
+                labels.push(meta.value()?.parse()?);
+                Ok(())
+            } else {
+                Err(meta.error("expected `label`"))
+            }
+        })?;
+    }
+    if labels.is_empty() {
+        return Err(syn::Error::new(input.ident.span(), "missing #[pem(label = \"...\")]"));
+    }
+    let field = match input.data {
+        syn::Data::Struct(ref data) if data.fields.len() == 1 => match data.fields.iter().next().unwrap().ident {
+            Some(ref name) => quote!(#name),
+            None => quote!(0),
+        },
+        _ => return Err(syn::Error::new(input.ident.span(), "only structs with one field are supported")),
+    };
+    Ok((field, labels))
+}
+
+fn derive(input: TokenStream, expand: fn(&DeriveInput, TokenStream2, Vec<LitStr>) -> TokenStream2) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match newtype_with_labels(&input) {
+        Ok((field, labels)) => expand(&input, field, labels).into(),
+        Err(e) => compile_error(e.span(), e.to_string()),
+    }
+}
+
+/// Derives `nom_pem::FromPem` for a newtype whose field implements `From<&[u8]>`.
+#[proc_macro_derive(FromPem, attributes(pem))]
+pub fn derive_from_pem(input: TokenStream) -> TokenStream {
+    derive(input, |input, field, labels| {
+        let name = &input.ident;
+        let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+        quote! {
+            impl #impl_generics ::nom_pem::FromPem for #name #ty_generics #where_clause {
+                const LABELS: &'static [&'static str] = &[#(#labels),*];
+
+                fn from_der(der: &[u8]) -> ::nom_pem::__private::Result<Self, ::nom_pem::PemParsingError> {
+                    ::nom_pem::__private::Result::Ok(#name { #field: ::nom_pem::__private::From::from(der) })
+                }
+            }
+        }
+    })
+}
+
+/// Derives `nom_pem::ToPem` for a newtype whose field implements `AsRef<[u8]>`,
+/// writing blocks with the first label.
+#[proc_macro_derive(ToPem, attributes(pem))]
+pub fn derive_to_pem(input: TokenStream) -> TokenStream {
+    derive(input, |input, field, labels| {
+        let name = &input.ident;
+        let label = &labels[0];
+        let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+        quote! {
+            impl #impl_generics ::nom_pem::ToPem for #name #ty_generics #where_clause {
+                const LABEL: &'static str = #label;
+
+                fn to_der(&self) -> ::nom_pem::__private::Vec<u8> {
+                    ::nom_pem::__private::AsRef::<[u8]>::as_ref(&self.#field).to_vec()
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+static BLOCK: &[u8] = b"-----BEGIN X-----\nTWFu\n-----END X-----";
+
+#[cfg(test)]
+#[test]
+fn test_decode() {
+    assert_eq!(Ok(vec![(String::from("X"), b"Man".to_vec())]), decode(BLOCK, Some("X")));
+    assert_eq!(Err(String::from("expected Y block, found X")), decode(BLOCK, Some("Y")));
+    assert!(decode(b"-----BEGIN X-----\nTW*u\n-----END X-----", None).is_err());
+    assert!(decode(b"no PEM", None).is_err());
+
+    let two = [BLOCK, b"\n", BLOCK].concat();
+    assert_eq!(2, decode(&two, None).unwrap().len());
+    assert_eq!(Err(String::from("expected one block, found 2, use a bundle macro for several")),
+               expand_block(&two, None).map(|t| t.to_string()));
+    assert!(expand_bundle(&two, Some("X")).is_ok());
+}
@@ -0,0 +1,318 @@
+//! OpenPGP ASCII Armor, see RFC 4880 section 6.
+//!
+//! Armor looks like PEM, but its headers are plain `Key: Value` lines that are always
+//! followed by an empty line, and the base64 data is followed by a `=XXXX` line
+//! holding the CRC-24 checksum of the data.
+
+#[cfg(not(feature = "std"))]
+use core::{fmt, str};
+#[cfg(feature = "std")]
+use std::{fmt, str};
+
+#[cfg(not(feature = "std"))]
+use alloc::{string::String, vec::Vec};
+use nom::{IResult, Err, ErrorKind, Needed};
+use super::{Block, BodyEncoding, HeaderEntry, PemParsingError, base64, nom_result};
+use super::parsers::{pem_begin, pem_footer};
+use super::base64::Decoder;
+use super::display::write_base64;
+
+const CRC24_INIT: u32 = 0x00b7_04ce;
+const CRC24_POLY: u32 = 0x0186_4cfb;
+
+/// CRC-24 checksum of the armored data, see RFC 4880 section 6.1
+pub fn crc24(data: &[u8]) -> u32 {
+    let mut crc = CRC24_INIT;
+    for &b in data.iter() {
+        crc ^= (b as u32) << 16;
+        for _ in 0..8 {
+            crc <<= 1;
+            if crc & 0x0100_0000 != 0 {
+                crc ^= CRC24_POLY;
+            }
+        }
+    }
+    crc & 0x00ff_ffff
+}
+
+#[cfg(test)]
+#[test]
+fn test_crc24() {
+    assert_eq!(0xb704ce, crc24(b""));
+    assert_eq!(0x21cf02, crc24(b"123456789"));
+}
+
+/// the kind of data an armor block holds, taken from its label
+#[derive(Debug, PartialEq, Clone)]
+pub enum ArmorType {
+    /// `PGP MESSAGE`
+    Message,
+    /// `PGP PUBLIC KEY BLOCK`
+    PublicKeyBlock,
+    /// `PGP PRIVATE KEY BLOCK`
+    PrivateKeyBlock,
+    /// `PGP SIGNATURE`
+    Signature,
+    /// `PGP MESSAGE, PART X/Y`, or `PGP MESSAGE, PART X` if the number of parts is unknown
+    MessagePart(u32, Option<u32>),
+}
+
+impl ArmorType {
+    /// maps an armor label to its type, `None` for labels not defined by RFC 4880
+    pub fn from_label(label: &str) -> Option<ArmorType> {
+        match label {
+            "PGP MESSAGE" => Some(ArmorType::Message),
+            "PGP PUBLIC KEY BLOCK" => Some(ArmorType::PublicKeyBlock),
+            "PGP PRIVATE KEY BLOCK" => Some(ArmorType::PrivateKeyBlock),
+            "PGP SIGNATURE" => Some(ArmorType::Signature),
+            _ if label.starts_with("PGP MESSAGE, PART ") => {
+                let part = &label["PGP MESSAGE, PART ".len()..];
+                let mut numbers = part.splitn(2, '/');
+                let x = numbers.next().and_then(|x| x.parse().ok())?;
+                match numbers.next() {
+                    Some(y) => y.parse().ok().map(|y| ArmorType::MessagePart(x, Some(y))),
+                    None => Some(ArmorType::MessagePart(x, None)),
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ArmorType {
+    /// writes the armor label
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ArmorType::Message => write!(f, "PGP MESSAGE"),
+            ArmorType::PublicKeyBlock => write!(f, "PGP PUBLIC KEY BLOCK"),
+            ArmorType::PrivateKeyBlock => write!(f, "PGP PRIVATE KEY BLOCK"),
+            ArmorType::Signature => write!(f, "PGP SIGNATURE"),
+            ArmorType::MessagePart(x, Some(y)) => write!(f, "PGP MESSAGE, PART {}/{}", x, y),
+            ArmorType::MessagePart(x, None) => write!(f, "PGP MESSAGE, PART {}", x),
+        }
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_armor_type() {
+    assert_eq!(Some(ArmorType::MessagePart(2, Some(3))), ArmorType::from_label("PGP MESSAGE, PART 2/3"));
+    assert_eq!(Some(ArmorType::MessagePart(2, None)), ArmorType::from_label("PGP MESSAGE, PART 2"));
+    assert_eq!(None, ArmorType::from_label("PGP MESSAGE, PART x/3"));
+    assert_eq!(None, ArmorType::from_label("PUBLIC KEY"));
+    assert_eq!("PGP MESSAGE, PART 2/3", format!("{}", ArmorType::MessagePart(2, Some(3))));
+}
+
+/// splits off the next line, without its line ending
+fn armor_line(i: &[u8]) -> IResult<&[u8], &[u8]> {
+    match i.iter().position(|&b| b == 10) {
+        Some(end) if end > 0 && i[end - 1] == 13 => Ok((&i[end + 1..], &i[..end - 1])),
+        Some(end) => Ok((&i[end + 1..], &i[..end])),
+        None => Err(Err::Incomplete(Needed::Size(1))),
+    }
+}
+
+/// reads a `Key: Value` armor header, the value is kept as one string
+pub fn armor_header(i: &[u8]) -> IResult<&[u8], HeaderEntry<'_>> {
+    let (rest, line) = armor_line(i)?;
+    let colon = match line.iter().position(|&b| b == b':') {
+        Some(colon) if colon > 0 && line[colon + 1..].starts_with(b" ") => colon,
+        _ => return Err(Err::Error(error_position!(i, ErrorKind::Custom(0xbb0301)))),
+    };
+    match (str::from_utf8(&line[..colon]), str::from_utf8(&line[colon + 2..])) {
+        (Ok(key), Ok(value)) => Ok((rest, HeaderEntry::Entry(key, vec![String::from(value)]))),
+        _ => Err(Err::Error(error_position!(i, ErrorKind::Custom(0xbb0302)))),
+    }
+}
+
+/// reads the armor headers and the empty line that ends them
+fn armor_headers(i: &[u8]) -> IResult<&[u8], Vec<HeaderEntry<'_>>> {
+    let (rest, headers) = fold_many0!(i, armor_header, Vec::new(), |mut headers: Vec<_>, header| { headers.push(header); headers })?;
+    match armor_line(rest)? {
+        (rest, []) => Ok((rest, headers)),
+        _ => Err(Err::Error(error_position!(rest, ErrorKind::Custom(0xbb0304)))),
+    }
+}
+
+fn is_checksum_line(line: &[u8]) -> bool {
+    line.len() == 5 && line[0] == b'=' && line[1..].iter().all(|&b| base64::STANDARD_DECODE[b as usize] < 64)
+}
+
+/// reads the base64 data up to the END line, and the checksum line if present
+fn armor_body<'a>(i: &'a [u8], label: &str) -> IResult<&'a [u8], (Vec<u8>, Option<u32>)> {
+    let decoder = Decoder::for_label(label, false);
+    let mut rest = i;
+    loop {
+        if rest.starts_with(b"-----") {
+            let data = base64_all(decoder, &i[..i.len() - rest.len()], i)?;
+            return Ok((rest, (data, None)));
+        }
+        let (next, line) = armor_line(rest)?;
+        if is_checksum_line(line) {
+            let data = base64_all(decoder, &i[..i.len() - rest.len()], i)?;
+            let checksum = line[1..].iter().fold(0u32, |a, &b| (a << 6) | base64::STANDARD_DECODE[b as usize] as u32);
+            return Ok((next, (data, Some(checksum))));
+        }
+        rest = next;
+    }
+}
+
+/// decodes the whole of `data`, failing on anything that is not base64
+fn base64_all<'a>(decoder: Decoder, data: &[u8], i: &'a [u8]) -> Result<Vec<u8>, Err<&'a [u8]>> {
+    match decoder.decode(data) {
+        Ok((rest, decoded)) if rest.iter().all(|&b| b == 10 || b == 13 || b == 32) => Ok(decoded),
+        _ => Err(Err::Error(error_position!(i, ErrorKind::Custom(0xbb0303)))),
+    }
+}
+
+// reads an armor block and the CRC-24 of its checksum line if there is one,
+// the checksum is not verified
+named!(pub pgp_armor<(Block, Option<u32>)>, do_parse!(
+    block_type: pem_begin >>
+    headers: armor_headers >>
+    body: call!(armor_body, block_type) >>
+    pem_footer >>
+    ((Block{block_type, headers, data: body.0, encoding: BodyEncoding::Base64, spans: None}, body.1))
+));
+
+/// Decodes an OpenPGP armor block and verifies its CRC-24 checksum.
+///
+/// The checksum line is optional, blocks with a label not defined by RFC 4880 are rejected.
+pub fn decode_armor<'a>(input: &'a [u8]) -> Result<Block<'a>, PemParsingError> {
+    let (block, checksum) = nom_result(pgp_armor(input))?;
+    verify_armor(block, checksum)
+}
+
+fn verify_armor(block: Block, checksum: Option<u32>) -> Result<Block, PemParsingError> {
+    if ArmorType::from_label(block.block_type).is_none() {
+        return Err(PemParsingError::UnexpectedBlockType(String::from(block.block_type)));
+    }
+    match checksum {
+        Some(checksum) if checksum != crc24(&block.data) => {
+            Err(PemParsingError::ArmorError(format!("checksum mismatch, expected {:06X} got {:06X}", checksum, crc24(&block.data))))
+        }
+        _ => Ok(block),
+    }
+}
+
+/// writes the headers one per line and the empty line that ends them
+fn write_armor_headers(f: &mut fmt::Formatter, headers: &[HeaderEntry]) -> fmt::Result {
+    for header in headers.iter() {
+        match *header {
+            HeaderEntry::Entry(key, ref values) => writeln!(f, "{}: {}", key, values.join(","))?,
+            ref other => writeln!(f, "{}", other)?,
+        }
+    }
+    writeln!(f)
+}
+
+/// Encodes a block as OpenPGP armor including the CRC-24 checksum line.
+///
+/// ```
+/// let b = b"-----BEGIN PGP MESSAGE-----
+/// Comment: Test, with comma
+///
+/// owFbw5PEmqtXUlGSdXV1ayIA
+/// =RkqZ
+/// -----END PGP MESSAGE-----
+/// ";
+/// let block = nom_pem::decode_armor(b).unwrap();
+/// assert_eq!(&b[..], format!("{}", nom_pem::PgpArmor(&block)).as_bytes());
+/// ```
+pub struct PgpArmor<'b, 'a: 'b>(pub &'b Block<'a>);
+
+impl<'b, 'a> fmt::Display for PgpArmor<'b, 'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "-----BEGIN {}-----", &self.0.block_type)?;
+        write_armor_headers(f, &self.0.headers)?;
+        write_base64(f, &self.0.data, 64)?;
+        let crc = crc24(&self.0.data);
+        write!(f, "=")?;
+        write_base64(f, &[(crc >> 16) as u8, (crc >> 8) as u8, crc as u8], 0)?;
+        writeln!(f)?;
+        writeln!(f, "-----END {}-----", &self.0.block_type)
+    }
+}
+
+/// an OpenPGP cleartext signed message, see RFC 4880 section 7
+#[derive(Debug, PartialEq)]
+pub struct CleartextSignedMessage<'a> {
+    /// the armor headers of the message, usually one or more `Hash` entries
+    pub headers: Vec<HeaderEntry<'a>>,
+    /// the signed text without dash escaping in canonical form, i.e. lines end with CRLF,
+    /// trailing whitespace is removed and the last line has no line ending
+    pub text: String,
+    /// the `PGP SIGNATURE` block following the text
+    pub signature: Block<'a>,
+}
+
+impl<'a> CleartextSignedMessage<'a> {
+    /// the hash algorithms named in the `Hash` headers, `MD5` if there is none
+    pub fn hashes(&self) -> Vec<&str> {
+        let hashes: Vec<&str> = self.headers.iter().flat_map(|header| match *header {
+            HeaderEntry::Entry("Hash", ref values) => values.iter().flat_map(|v| v.split(',')).map(|v| v.trim()).collect(),
+            _ => Vec::new(),
+        }).collect();
+        if hashes.is_empty() { vec!["MD5"] } else { hashes }
+    }
+}
+
+static SIGNATURE_START: &[u8] = b"-----BEGIN PGP SIGNATURE-----";
+
+/// reads the dash escaped text up to the signature, returns the canonical text
+fn cleartext(i: &[u8]) -> IResult<&[u8], String> {
+    let mut text = String::new();
+    let mut rest = i;
+    while !rest.starts_with(SIGNATURE_START) {
+        let (next, mut line) = armor_line(rest)?;
+        if line.starts_with(b"- ") {
+            line = &line[2..];
+        } else if line.starts_with(b"-") {
+            return Err(Err::Error(error_position!(rest, ErrorKind::Custom(0xbb0305))));
+        }
+        let line = match str::from_utf8(line) {
+            Ok(line) => line.trim_end_matches(&[' ', '\t'][..]),
+            Err(_) => return Err(Err::Error(error_position!(rest, ErrorKind::Custom(0xbb0306)))),
+        };
+        if rest.len() != i.len() {
+            text.push_str("\r\n");
+        }
+        text.push_str(line);
+        rest = next;
+    }
+    Ok((rest, text))
+}
+
+named!(pub pgp_signed_message<(CleartextSignedMessage, Option<u32>)>, do_parse!(
+    verify!(pem_begin, |label: &str| label == "PGP SIGNED MESSAGE") >>
+    headers: armor_headers >>
+    text: cleartext >>
+    signature: pgp_armor >>
+    ((CleartextSignedMessage{headers, text, signature: signature.0}, signature.1))
+));
+
+/// Decodes a `PGP SIGNED MESSAGE` and verifies the CRC-24 checksum of its signature.
+pub fn decode_signed_message<'a>(input: &'a [u8]) -> Result<CleartextSignedMessage<'a>, PemParsingError> {
+    let (message, checksum) = nom_result(pgp_signed_message(input))?;
+    if message.signature.block_type != "PGP SIGNATURE" {
+        return Err(PemParsingError::UnexpectedBlockType(String::from(message.signature.block_type)));
+    }
+    let signature = verify_armor(message.signature, checksum)?;
+    Ok(CleartextSignedMessage { headers: message.headers, text: message.text, signature })
+}
+
+impl<'a> fmt::Display for CleartextSignedMessage<'a> {
+    /// encodes the message, lines of the text starting with a dash are dash escaped
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "-----BEGIN PGP SIGNED MESSAGE-----")?;
+        write_armor_headers(f, &self.headers)?;
+        for line in self.text.split('\n') {
+            let line = line.trim_end_matches('\r');
+            if line.starts_with('-') {
+                write!(f, "- ")?;
+            }
+            writeln!(f, "{}", line)?;
+        }
+        write!(f, "{}", PgpArmor(&self.signature))
+    }
+}
@@ -1,8 +1,13 @@
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
 use nom::IResult;
+#[cfg(feature = "zeroize")]
+use zeroize::Zeroize;
 
 
 pub fn base64(input: &[u8]) -> IResult<&[u8], Vec<u8>> {
-    let mut ret: Vec<u8> = Vec::with_capacity(input.len() / 4 * 3);
+    // sized exactly, so that no copy of the data is left behind by a reallocation
+    let mut ret: Vec<u8> = Vec::with_capacity(base64_decoded_len(input));
     let mut reg = 0u16;
     let mut bits = 0u8;
     let mut pos = 0;
@@ -18,15 +23,206 @@ pub fn base64(input: &[u8]) -> IResult<&[u8], Vec<u8>> {
             ret.push((reg >> bits) as u8);
         }
     }
+    #[cfg(feature = "zeroize")]
+    reg.zeroize();
     while pos < input.len() && input[pos] == 61 { pos += 1 } // remove the padding
     Ok((&input[pos..], ret))
 }
 
+/// all ones if `a < b`, for values below 2^31
+fn ct_lt(a: u32, b: u32) -> u32 {
+    (a.wrapping_sub(b) >> 31).wrapping_neg()
+}
+
+/// all ones if `a == b`, for byte values
+fn ct_eq(a: u32, b: u32) -> u32 {
+    ((a ^ b).wrapping_sub(1) >> 31).wrapping_neg()
+}
+
+/// all ones if `lo <= c <= hi`
+fn ct_in_range(c: u32, lo: u32, hi: u32) -> u32 {
+    !ct_lt(c, lo) & !ct_lt(hi, c)
+}
+
+/// decodes a base64 character without table lookups and branches,
+/// returns the value, a mask for valid characters and a mask for ignored whitespace
+fn ct_decode_char(c: u8) -> (u32, u32, u32) {
+    let c = c as u32;
+    let upper = ct_in_range(c, 65, 90);
+    let lower = ct_in_range(c, 97, 122);
+    let digit = ct_in_range(c, 48, 57);
+    let plus = ct_eq(c, 43);
+    let slash = ct_eq(c, 47);
+    let value = (upper & c.wrapping_sub(65)) | (lower & c.wrapping_sub(71)) | (digit & c.wrapping_add(4)) | (plus & 62) | (slash & 63);
+    let ignored = ct_eq(c, 9) | ct_eq(c, 10) | ct_eq(c, 13) | ct_eq(c, 32);
+    (value, upper | lower | digit | plus | slash, ignored)
+}
+
+/// Decodes like `base64`, but in constant time with respect to the base64 characters,
+/// i.e. without indexing tables by them and without branching on their class.
+///
+/// The body ends at the first dash, which never occurs in base64, so that the END line
+/// and the blocks following it are not scanned.
+pub fn base64_constant_time(input: &[u8]) -> IResult<&[u8], Vec<u8>> {
+    let mut ret = vec![0u8; base64_constant_time_len(input)];
+    let (rest, _) = base64_constant_time_into(input, &mut ret);
+    Ok((rest, ret))
+}
+
+/// number of bytes `base64_constant_time` decodes from `input`, counted in constant time
+pub fn base64_constant_time_len(input: &[u8]) -> usize {
+    let limit = input.iter().position(|&b| b == b'-').unwrap_or(input.len());
+    let mut symbols = 0usize;
+    let mut done = 0u32;
+    for &c in input[..limit].iter() {
+        let (_, valid, ignored) = ct_decode_char(c);
+        done |= !valid & !ignored;
+        symbols += (valid & !done & 1) as usize;
+    }
+    symbols * 6 / 8
+}
+
+/// decodes like `base64_constant_time` into `out`, which has to hold
+/// `base64_constant_time_len(input)` bytes, returns the remaining input and the number of bytes written
+pub fn base64_constant_time_into<'a>(input: &'a [u8], out: &mut [u8]) -> (&'a [u8], usize) {
+    let limit = input.iter().position(|&b| b == b'-').unwrap_or(input.len());
+    let last = out.len().saturating_sub(1);
+    let mut len = 0usize;
+    let mut reg = 0u32;
+    let mut bits = 0u32;
+    let mut done = 0u32;
+    let mut end = limit;
+    for (pos, &c) in input[..limit].iter().enumerate() {
+        let (value, valid, ignored) = ct_decode_char(c);
+        let stop = !valid & !ignored & !done;
+        let stop_mask = ((stop & 1) as usize).wrapping_neg();
+        end = (end & !stop_mask) | (pos & stop_mask);
+        done |= stop;
+        let take = valid & !done;
+        reg = ((((reg << 6) | value) & 0xffff) & take) | (reg & !take);
+        bits = ((bits + 6) & take) | (bits & !take);
+        let emit = !ct_lt(bits, 8);
+        bits -= 8 & emit;
+        // every character stores to `out`, bytes that are not emitted keep the old value
+        if !out.is_empty() {
+            let i = len.min(last);
+            out[i] = (((reg >> bits) & emit) | (out[i] as u32 & !emit)) as u8;
+        }
+        len += (1 & emit) as usize;
+    }
+    #[cfg(feature = "zeroize")]
+    reg.zeroize();
+    let mut pos = end;
+    while pos < input.len() && input[pos] == 61 { pos += 1 } // remove the padding
+    (&input[pos..], len)
+}
+
+/// the base64 decoder for the data of a block
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Decoder {
+    /// `base64`, `base64_into`
+    Table,
+    /// `base64_constant_time`, `base64_constant_time_into`
+    ConstantTime,
+}
+
+impl Decoder {
+    /// the decoder of every path reading block data, constant time for labels containing
+    /// `PRIVATE KEY`, e.g. `RSA PRIVATE KEY`, `OPENSSH PRIVATE KEY` or `PGP PRIVATE KEY BLOCK`,
+    /// or if `constant_time` is requested
+    pub fn for_label(label: &str, constant_time: bool) -> Decoder {
+        if constant_time || label.contains("PRIVATE KEY") { Decoder::ConstantTime } else { Decoder::Table }
+    }
+
+    pub fn decode(self, input: &[u8]) -> IResult<&[u8], Vec<u8>> {
+        match self {
+            Decoder::Table => base64(input),
+            Decoder::ConstantTime => base64_constant_time(input),
+        }
+    }
+
+    pub fn decoded_len(self, input: &[u8]) -> usize {
+        match self {
+            Decoder::Table => base64_decoded_len(input),
+            Decoder::ConstantTime => base64_constant_time_len(input),
+        }
+    }
+
+    pub fn decode_into<'a>(self, input: &'a [u8], out: &mut [u8]) -> (&'a [u8], usize) {
+        match self {
+            Decoder::Table => base64_into(input, out),
+            Decoder::ConstantTime => base64_constant_time_into(input, out),
+        }
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_base64_constant_time() {
+    for c in 0..=255u8 {
+        let (value, valid, ignored) = ct_decode_char(c);
+        match STANDARD_DECODE[c as usize] {
+            X => assert_eq!((0, 0), (valid, ignored)),
+            I => assert_eq!((0, 0xffff_ffff), (valid, ignored)),
+            v => assert_eq!((v as u32, 0xffff_ffff, 0), (value, valid, ignored)),
+        }
+    }
+    for b in [&b"TWFu\nTWE=\n-----END"[..], b"TWFu TW\n-X", b"TWFuTQ==", b"", b"TWF*u"].iter() {
+        assert_eq!(base64(b), base64_constant_time(b));
+        assert_eq!(base64_decoded_len(b), base64_constant_time_len(b));
+        let mut out = vec![0u8; base64_constant_time_len(b)];
+        let (rest, len) = base64_constant_time_into(b, &mut out);
+        assert_eq!(base64(b), Ok((rest, out[..len].to_vec())));
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_decoder_for_label() {
+    for label in ["PRIVATE KEY", "RSA PRIVATE KEY", "EC PRIVATE KEY", "OPENSSH PRIVATE KEY", "PGP PRIVATE KEY BLOCK"].iter() {
+        assert_eq!(Decoder::ConstantTime, Decoder::for_label(label, false));
+    }
+    assert_eq!(Decoder::Table, Decoder::for_label("CERTIFICATE", false));
+    assert_eq!(Decoder::ConstantTime, Decoder::for_label("CERTIFICATE", true));
+}
+
+/// number of bytes `base64` decodes from `input`
+pub fn base64_decoded_len(input: &[u8]) -> usize {
+    let symbols = input.iter().map(|&c| STANDARD_DECODE[c as usize]).take_while(|&b| b != X).filter(|&b| b != I).count();
+    symbols * 6 / 8
+}
+
+/// decodes like `base64`, but into `out`, which has to hold `base64_decoded_len(input)` bytes,
+/// returns the remaining input and the number of bytes written
+pub fn base64_into<'a>(input: &'a [u8], out: &mut [u8]) -> (&'a [u8], usize) {
+    let mut written = 0;
+    let mut reg = 0u16;
+    let mut bits = 0u8;
+    let mut pos = 0;
+    while pos < input.len() {
+        let b = STANDARD_DECODE[input[pos] as usize];
+        if b == X { break; }
+        pos += 1;
+        if b == I { continue; }
+        reg = (reg << 6) | b as u16;
+        bits += 6;
+        if bits >= 8 {
+            bits %= 8;
+            out[written] = (reg >> bits) as u8;
+            written += 1;
+        }
+    }
+    #[cfg(feature = "zeroize")]
+    reg.zeroize();
+    while pos < input.len() && input[pos] == 61 { pos += 1 } // remove the padding
+    (&input[pos..], written)
+}
+
 const X: u8 = 0xff; // INVALID
 const I: u8 = 0xfe;  // IGNORE
 
 
-pub const STANDARD_DECODE: &'static [u8; 256] = &[
+pub const STANDARD_DECODE: &[u8; 256] = &[
     X, X, X, X, X, X, X, X, X, I, I, X, X, I, X, X, X, X, X, X, X, X, X, X, X, X, X, X, X, X, X, X, I,
     X, X, X, X, X, X, X, X, X, X, 62, X, X, X, 63, 52, 53, 54, 55, 56, 57, 58, 59, 60, 61, X, X, X, X,
     X, X, X, 0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25,
@@ -46,11 +242,22 @@ J6UiRRGcDSvzrsoK+oNvqu6z7Xs5Xfz5rDqUcMlK1Z6720dcBWGGsDLpTpSCnpot
 dXd/H5LMDWnonNvPCwQUHt==";
 
     let r = base64(b);
-    assert_eq!(r.is_ok(), true);
+    assert!(r.is_ok());
     let (rest, result) = r.unwrap();
     assert_eq!(&rest, &[]);
 
     assert_eq!(result.len(), 160);
     assert_eq!(&result[0..8], &[44u8, 186, 199, 7, 71, 137, 207, 40][..]);
     assert_eq!(&result[150..160], &[13u8, 105, 232, 156, 219, 207, 11, 4, 20, 30][..]);
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+#[test]
+fn test_base64_into() {
+    let b = b"TWFu\nTWE=\nX";
+    let mut out = [0u8; 5];
+    assert_eq!(5, base64_decoded_len(b));
+    assert_eq!((&b"\nX"[..], 5), base64_into(b, &mut out));
+    assert_eq!(b"ManMa", &out);
+    assert_eq!(base64(b).unwrap().1.len(), base64_decoded_len(b));
+}
@@ -0,0 +1,209 @@
+//! Decoding without heap allocations, the data is decoded into a buffer of the caller
+//! and the headers are read from the input on demand.
+
+#[cfg(not(feature = "std"))]
+use core::{fmt, str};
+#[cfg(feature = "std")]
+use std::{error, fmt, str};
+
+use nom::{ErrorKind, IResult};
+use super::BodyEncoding;
+use super::base64::Decoder;
+use super::parsers::{pem_begin, pem_footer, cleanup_spaces, PEM_END};
+
+/// an error of `decode_block_into`, which does not allocate either
+#[derive(Debug, PartialEq, Clone)]
+pub enum BlockRefError {
+    /// the input is not a PEM block
+    Parse(ErrorKind),
+    /// a line of the clear text of a MIC-CLEAR message starts with an unescaped dash
+    UnescapedDash,
+    /// the buffer cannot hold the decoded data, which needs the given number of bytes
+    BufferTooSmall(usize),
+}
+
+impl fmt::Display for BlockRefError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            BlockRefError::Parse(ref e) => write!(f, "parsing error: {}", e.description()),
+            BlockRefError::UnescapedDash => write!(f, "unescaped dash in clear text"),
+            BlockRefError::BufferTooSmall(n) => write!(f, "buffer too small, {} bytes required", n),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl error::Error for BlockRefError {}
+
+/// a PEM block whose data lives in a buffer of the caller, see `decode_block_into`
+#[derive(Debug, PartialEq)]
+pub struct BlockRef<'i, 'b> {
+    pub block_type: &'i str,
+    /// the decoded data, or the clear text of MIC-CLEAR messages
+    pub data: &'b [u8],
+    pub encoding: BodyEncoding,
+    /// the header lines of the input including the empty line that ends them
+    raw_headers: &'i [u8],
+}
+
+impl<'i, 'b> BlockRef<'i, 'b> {
+    /// iterates over the headers of the block as they appear in the input
+    pub fn headers(&self) -> HeaderRefs<'i> {
+        HeaderRefs { rest: self.raw_headers }
+    }
+}
+
+/// one header as it appears in the input
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct HeaderRef<'i> {
+    pub key: &'i str,
+    /// the value without the spaces after the colon and without the final line ending,
+    /// continuation lines are kept with their line endings and leading whitespace
+    pub value: &'i str,
+}
+
+impl<'i> HeaderRef<'i> {
+    /// the comma separated fields of the value
+    pub fn fields(&self) -> str::Split<'i, char> {
+        self.value.split(',')
+    }
+}
+
+/// iterator over the headers of a `BlockRef`
+#[derive(Debug, Clone)]
+pub struct HeaderRefs<'i> {
+    rest: &'i [u8],
+}
+
+impl<'i> Iterator for HeaderRefs<'i> {
+    type Item = HeaderRef<'i>;
+
+    fn next(&mut self) -> Option<HeaderRef<'i>> {
+        let colon = self.rest.iter().position(|&b| b == b':')?;
+        let key = str::from_utf8(&self.rest[..colon]).ok()?;
+        let mut start = colon + 1;
+        while start < self.rest.len() && (self.rest[start] == b' ' || self.rest[start] == b'\t') {
+            start += 1;
+        }
+        let mut end = start;
+        loop {
+            end += self.rest[end..].iter().position(|&b| b == 10)?;
+            // continuation lines start with whitespace
+            match self.rest.get(end + 1) {
+                Some(&b' ') | Some(&b'\t') => end += 1,
+                _ => break,
+            }
+        }
+        let line_end = if end > start && self.rest[end - 1] == 13 { end - 1 } else { end };
+        let value = str::from_utf8(&self.rest[start..line_end]).ok()?;
+        self.rest = &self.rest[end + 1..];
+        Some(HeaderRef { key, value })
+    }
+}
+
+/// length of the header section including the empty line that ends it, 0 if there are no headers
+fn header_section_len(i: &[u8]) -> usize {
+    let first_line = &i[..i.iter().position(|&b| b == 10).unwrap_or(i.len())];
+    let is_header = match first_line.iter().position(|&b| b == b':') {
+        Some(colon) => colon > 0 && first_line[..colon].iter().all(|&b| b.is_ascii_alphabetic() || b == b'-'),
+        None => false,
+    };
+    if !is_header {
+        return 0;
+    }
+    let mut pos = 0;
+    while pos < i.len() {
+        let end = match i[pos..].iter().position(|&b| b == 10) {
+            Some(end) => pos + end + 1,
+            None => return i.len(),
+        };
+        let line = &i[pos..end];
+        if line == b"\n" || line == b"\r\n" {
+            return end;
+        }
+        if line.starts_with(PEM_END) {
+            return pos;
+        }
+        pos = end;
+    }
+    i.len()
+}
+
+fn is_mic_clear(raw_headers: &[u8]) -> bool {
+    (HeaderRefs { rest: raw_headers }).any(|h| h.key == "Proc-Type" && h.value.ends_with("MIC-CLEAR"))
+}
+
+/// copies the dash unescaped text up to the END line into `out` as far as it fits,
+/// returns the rest of the input and the length of the text
+fn cleartext_into<'a>(i: &'a [u8], out: &mut [u8]) -> Result<(&'a [u8], usize), BlockRefError> {
+    let mut len = 0;
+    let mut line_ending = 0;
+    let mut rest = i;
+    while !rest.starts_with(PEM_END) {
+        let end = match rest.iter().position(|&b| b == 10) {
+            Some(end) => end + 1,
+            None => return Err(BlockRefError::Parse(ErrorKind::Complete)),
+        };
+        let mut line = &rest[..end];
+        if line.starts_with(b"- ") {
+            line = &line[2..];
+        } else if line.starts_with(b"-") {
+            return Err(BlockRefError::UnescapedDash);
+        }
+        if len + line.len() <= out.len() {
+            out[len..len + line.len()].copy_from_slice(line);
+        }
+        len += line.len();
+        line_ending = if line.ends_with(b"\r\n") { 2 } else { 1 };
+        rest = &rest[end..];
+    }
+    // the line ending before the END line belongs to the boundary
+    Ok((rest, len - line_ending))
+}
+
+/// splits a parser result into the remaining input and the value
+fn parse<T>(result: IResult<&[u8], T>) -> Result<(&[u8], T), BlockRefError> {
+    result.map_err(|e| BlockRefError::Parse(e.into_error_kind()))
+}
+
+/// Decodes a PEM block without allocating, the data is written to `buffer`.
+///
+/// Fails with `BlockRefError::BufferTooSmall` holding the required size if `buffer`
+/// cannot hold the data.
+///
+/// ```
+/// let b = b"-----BEGIN MESSAGE-----
+/// Comment: hello
+///
+/// aGVsbG8gd29ybGQ=
+/// -----END MESSAGE-----
+/// ";
+/// let mut buffer = [0u8; 16];
+/// let block = nom_pem::decode_block_into(b, &mut buffer).unwrap();
+/// assert_eq!(b"hello world", block.data);
+/// assert_eq!("hello", block.headers().next().unwrap().value);
+/// ```
+pub fn decode_block_into<'i, 'b>(input: &'i [u8], buffer: &'b mut [u8]) -> Result<BlockRef<'i, 'b>, BlockRefError> {
+    let (rest, block_type) = parse(pem_begin(input))?;
+    let headers_len = header_section_len(rest);
+    let (raw_headers, body) = rest.split_at(headers_len);
+    let (rest, len, encoding) = if is_mic_clear(raw_headers) {
+        let (rest, len) = cleartext_into(body, buffer)?;
+        (rest, len, BodyEncoding::Clear)
+    } else {
+        let decoder = Decoder::for_label(block_type, false);
+        let (body, _) = parse(cleanup_spaces(body))?;
+        let len = decoder.decoded_len(body);
+        if len > buffer.len() {
+            return Err(BlockRefError::BufferTooSmall(len));
+        }
+        let (rest, written) = decoder.decode_into(body, buffer);
+        let (rest, _) = parse(cleanup_spaces(rest))?;
+        (rest, written, BodyEncoding::Base64)
+    };
+    if len > buffer.len() {
+        return Err(BlockRefError::BufferTooSmall(len));
+    }
+    parse(pem_footer(rest))?;
+    Ok(BlockRef { block_type, data: &buffer[..len], encoding, raw_headers })
+}
@@ -0,0 +1,87 @@
+//! Canonical form of `Content-Domain: RFC822` text, see RFC 1421 section 4.3.2.2.
+//!
+//! The MIC of a message is computed over the canonical form of its text: 7-bit ASCII
+//! with every line, including the last one, terminated by CRLF.
+
+#[cfg(not(feature = "std"))]
+use alloc::{string::String, vec::Vec};
+use super::{Block, BodyEncoding, HeaderEntry, PemParsingError, ProcTypeType};
+
+/// Converts text from local form to canonical form.
+///
+/// LF, CR and CRLF line endings become CRLF and a missing line ending after the last
+/// line is added. Fails on bytes outside of 7-bit ASCII and on NUL.
+pub fn canonicalize(text: &[u8]) -> Result<Vec<u8>, PemParsingError> {
+    let mut canonical = Vec::with_capacity(text.len() + text.len() / 32 + 2);
+    let mut pos = 0;
+    while pos < text.len() {
+        match text[pos] {
+            b'\r' => {
+                if text.get(pos + 1) == Some(&b'\n') {
+                    pos += 1;
+                }
+                canonical.extend_from_slice(b"\r\n");
+            }
+            b'\n' => canonical.extend_from_slice(b"\r\n"),
+            0 | 0x80..=0xff => {
+                return Err(PemParsingError::TextError(format!("byte {:#04x} at position {} is not RFC822 text", text[pos], pos)));
+            }
+            b => canonical.push(b),
+        }
+        pos += 1;
+    }
+    if !canonical.is_empty() && !canonical.ends_with(b"\r\n") {
+        canonical.extend_from_slice(b"\r\n");
+    }
+    Ok(canonical)
+}
+
+/// Converts text from canonical form to local form with LF line endings.
+pub fn decanonicalize(text: &[u8]) -> Vec<u8> {
+    let mut local = Vec::with_capacity(text.len());
+    for (pos, &b) in text.iter().enumerate() {
+        if b != b'\r' || text.get(pos + 1) != Some(&b'\n') {
+            local.push(b);
+        }
+    }
+    local
+}
+
+#[cfg(test)]
+#[test]
+fn test_canonicalize() {
+    assert_eq!(b"a\r\nb\r\nc\r\n\r\n".to_vec(), canonicalize(b"a\nb\rc\r\n\n").unwrap());
+    assert_eq!(b"a\r\n".to_vec(), canonicalize(b"a").unwrap());
+    assert_eq!(Vec::<u8>::new(), canonicalize(b"").unwrap());
+    assert!(canonicalize("gr\u{fc}n".as_bytes()).is_err());
+    assert_eq!(b"a\nb\rc\n".to_vec(), decanonicalize(b"a\r\nb\rc\r\n"));
+}
+
+impl<'a> Block<'a> {
+    /// The canonical form of the text of a `MIC-ONLY` or `MIC-CLEAR` message,
+    /// i.e. the input of its MIC.
+    ///
+    /// `MIC-ONLY` bodies are already canonical, the clear text of `MIC-CLEAR` bodies
+    /// is canonicalized. Fails for other messages and for a `Content-Domain` other than `RFC822`.
+    pub fn canonical_text(&self) -> Result<Vec<u8>, PemParsingError> {
+        let mut proc_type = None;
+        for header in self.headers.iter() {
+            match *header {
+                HeaderEntry::ProcType(_, ref t) => proc_type = Some(t),
+                HeaderEntry::ContentDomain(ref domain) if domain != "RFC822" => {
+                    return Err(PemParsingError::TextError(format!("unsupported Content-Domain {}", domain)));
+                }
+                _ => {}
+            }
+        }
+        match proc_type {
+            Some(&ProcTypeType::MIC_ONLY) | Some(&ProcTypeType::MIC_CLEAR) => {}
+            Some(t) => return Err(PemParsingError::UnexpectedProcType(format!("{}", t))),
+            None => return Err(PemParsingError::UnexpectedProcType(String::new())),
+        }
+        match self.encoding {
+            BodyEncoding::Clear => canonicalize(&self.data),
+            BodyEncoding::Base64 => Ok(self.data.clone()),
+        }
+    }
+}
@@ -0,0 +1,121 @@
+//! Ordering of the certificates of a PEM bundle into a chain from leaf to root.
+
+#[cfg(not(feature = "std"))]
+use alloc::{string::String, vec::Vec};
+use super::{Block, PemParsingError};
+use super::x509::{parse_certificate, format_name, CERTIFICATE_LABELS};
+
+/// certificates of a bundle, ordered from leaf to root
+#[derive(Debug)]
+pub struct CertificateChain<'b, 'a: 'b> {
+    /// the chain, starting with the leaf certificate
+    pub chain: Vec<&'b Block<'a>>,
+    /// issuer of the last certificate in the chain if it is not self issued
+    /// and its issuer is not part of the bundle
+    pub missing_issuer: Option<String>,
+    /// certificates of the bundle that are not part of the chain
+    pub extra: Vec<&'b Block<'a>>,
+    /// exact copies of certificates already present in the bundle
+    pub duplicates: Vec<&'b Block<'a>>,
+}
+
+impl<'b, 'a> CertificateChain<'b, 'a> {
+    /// true if the chain ends with a self issued certificate
+    pub fn is_complete(&self) -> bool {
+        !self.chain.is_empty() && self.missing_issuer.is_none()
+    }
+
+    /// encodes the ordered chain as PEM
+    pub fn to_pem(&self) -> String {
+        self.chain.iter().map(|block| format!("{}", block)).collect()
+    }
+}
+
+struct Node<'b, 'a: 'b> {
+    block: &'b Block<'a>,
+    subject: &'b [u8],
+    issuer: &'b [u8],
+    subject_key_id: Option<&'b [u8]>,
+    authority_key_id: Option<&'b [u8]>,
+}
+
+impl<'b, 'a> Node<'b, 'a> {
+    fn issued_by(&self, parent: &Node) -> bool {
+        if self.issuer != parent.subject {
+            return false;
+        }
+        match (self.authority_key_id, parent.subject_key_id) {
+            (Some(aki), Some(ski)) => aki == ski,
+            _ => true,
+        }
+    }
+
+    fn is_self_issued(&self) -> bool {
+        self.issued_by(self)
+    }
+}
+
+/// follows the issuers starting at `leaf`, returns the indices and whether the chain is complete
+fn follow(nodes: &[Node], leaf: usize) -> (Vec<usize>, bool) {
+    let mut chain = vec![leaf];
+    loop {
+        let current = &nodes[chain[chain.len() - 1]];
+        if current.is_self_issued() {
+            return (chain, true);
+        }
+        match (0..nodes.len()).find(|&i| !chain.contains(&i) && current.issued_by(&nodes[i])) {
+            Some(i) => chain.push(i),
+            None => return (chain, false),
+        }
+    }
+}
+
+/// Orders the CERTIFICATE blocks of a bundle from leaf to root.
+///
+/// Issuers are matched by their subject name, and by the key identifier if both
+/// certificates carry one. Blocks of other types are ignored.
+pub fn order_chain<'b, 'a>(blocks: &'b [Block<'a>]) -> Result<CertificateChain<'b, 'a>, PemParsingError> {
+    let mut nodes: Vec<Node> = Vec::new();
+    let mut duplicates = Vec::new();
+    for block in blocks.iter().filter(|b| CERTIFICATE_LABELS.contains(&b.block_type)) {
+        if nodes.iter().any(|n| n.block.data == block.data) {
+            duplicates.push(block);
+            continue;
+        }
+        let cert = parse_certificate(&block.data)?;
+        nodes.push(Node {
+            block,
+            subject: cert.subject,
+            issuer: cert.issuer,
+            subject_key_id: cert.subject_key_id()?,
+            authority_key_id: cert.authority_key_id()?,
+        });
+    }
+    if nodes.is_empty() {
+        return Ok(CertificateChain { chain: Vec::new(), missing_issuer: None, extra: Vec::new(), duplicates });
+    }
+
+    // leaf candidates are certificates that did not issue any other certificate of the bundle,
+    // the one with the longest chain wins
+    let mut best: Option<(Vec<usize>, bool)> = None;
+    for leaf in 0..nodes.len() {
+        let has_child = (0..nodes.len()).any(|i| i != leaf && nodes[i].issued_by(&nodes[leaf]));
+        if has_child {
+            continue;
+        }
+        let candidate = follow(&nodes, leaf);
+        if best.as_ref().map(|b| candidate.0.len() > b.0.len()).unwrap_or(true) {
+            best = Some(candidate);
+        }
+    }
+    let (indices, complete) = best.unwrap_or_else(|| follow(&nodes, 0));
+
+    let missing_issuer = if complete {
+        None
+    } else {
+        Some(format_name(nodes[indices[indices.len() - 1]].issuer)?)
+    };
+    let extra = (0..nodes.len()).filter(|i| !indices.contains(i)).map(|i| nodes[i].block).collect();
+    let chain = indices.iter().map(|&i| nodes[i].block).collect();
+    Ok(CertificateChain { chain, missing_issuer, extra, duplicates })
+}
@@ -0,0 +1,139 @@
+//! Conversion between the PKCS#1, SEC1 and PKCS#8 private key containers.
+
+#[cfg(not(feature = "std"))]
+use alloc::{string::String, vec::Vec};
+use super::{Block, BodyEncoding, PemParsingError};
+use super::der::*;
+use super::keys::{is_encrypted, parse_pkcs8};
+#[cfg(feature = "zeroize")]
+use zeroize::Zeroize;
+
+/// checks the label and that the key is not encrypted, whose data cannot be rewrapped
+fn expect_block_type(block: &Block, block_type: &str) -> Result<(), PemParsingError> {
+    if block.block_type != block_type {
+        Err(PemParsingError::UnexpectedBlockType(String::from(block.block_type)))
+    } else if is_encrypted(block) {
+        Err(PemParsingError::EncryptedBlock(String::from(block.block_type)))
+    } else {
+        Ok(())
+    }
+}
+
+fn private_key_block(block_type: &'static str, data: Vec<u8>) -> Block<'static> {
+    Block { block_type, headers: Vec::new(), data, encoding: BodyEncoding::Base64, spans: None }
+}
+
+/// PrivateKeyInfo (RFC 5208) with the given algorithm identifier content
+fn pkcs8(algorithm: &[u8], private_key: &[u8]) -> Vec<u8> {
+    let mut info = Vec::with_capacity(algorithm.len() + private_key.len() + 15);
+    write_tlv(&mut info, TAG_INTEGER, &[0]);
+    write_tlv(&mut info, TAG_SEQUENCE, algorithm);
+    write_tlv(&mut info, TAG_OCTET_STRING, private_key);
+    let ret = tlv(TAG_SEQUENCE, &info);
+    #[cfg(feature = "zeroize")]
+    info.zeroize();
+    ret
+}
+
+/// Wraps an `RSA PRIVATE KEY` (PKCS#1) into a `PRIVATE KEY` (PKCS#8) block.
+pub fn pkcs1_to_pkcs8(key: &Block) -> Result<Block<'static>, PemParsingError> {
+    expect_block_type(key, "RSA PRIVATE KEY")?;
+    DerReader::new(&key.data).read_sequence()?;
+    let mut algorithm = tlv(TAG_OID, OID_RSA_ENCRYPTION);
+    write_tlv(&mut algorithm, TAG_NULL, &[]);
+    Ok(private_key_block("PRIVATE KEY", pkcs8(&algorithm, &key.data)))
+}
+
+/// Unwraps the `RSA PRIVATE KEY` (PKCS#1) from a `PRIVATE KEY` (PKCS#8) block.
+pub fn pkcs8_to_pkcs1(key: &Block) -> Result<Block<'static>, PemParsingError> {
+    expect_block_type(key, "PRIVATE KEY")?;
+    let (oid, _, private_key) = parse_pkcs8(&key.data)?;
+    if oid != OID_RSA_ENCRYPTION {
+        return Err(PemParsingError::DerError(format!("not an RSA key: {}", oid_name(oid))));
+    }
+    Ok(private_key_block("RSA PRIVATE KEY", private_key.to_vec()))
+}
+
+/// fields of a SEC1 ECPrivateKey (RFC 5915) as raw TLVs, the parameters without their `[0]` wrapper
+struct Sec1<'a> {
+    version: &'a [u8],
+    private_key: &'a [u8],
+    parameters: Option<&'a [u8]>,
+    public_key: Option<&'a [u8]>,
+}
+
+impl<'a> Sec1<'a> {
+    fn parse(der: &'a [u8]) -> Result<Sec1<'a>, PemParsingError> {
+        let mut key = DerReader::new(der).read_sequence()?;
+        let version = key.read_raw()?;
+        let private_key = key.read_raw()?;
+        let parameters = match key.read_optional(tag_explicit(0))? {
+            Some(parameters) => Some(DerReader::new(parameters).read_raw()?),
+            None => None,
+        };
+        let public_key = if key.peek_tag() == Some(tag_explicit(1)) { Some(key.read_raw()?) } else { None };
+        Ok(Sec1 { version, private_key, parameters, public_key })
+    }
+
+    fn encode(&self, parameters: Option<&[u8]>) -> Vec<u8> {
+        let length = self.version.len() + self.private_key.len() + parameters.map_or(0, |p| p.len() + 6)
+            + self.public_key.map_or(0, |p| p.len());
+        let mut key = Vec::with_capacity(length);
+        key.extend_from_slice(self.version);
+        key.extend_from_slice(self.private_key);
+        if let Some(parameters) = parameters {
+            write_tlv(&mut key, tag_explicit(0), parameters);
+        }
+        if let Some(public_key) = self.public_key {
+            key.extend_from_slice(public_key);
+        }
+        let ret = tlv(TAG_SEQUENCE, &key);
+        #[cfg(feature = "zeroize")]
+        key.zeroize();
+        ret
+    }
+}
+
+/// Wraps an `EC PRIVATE KEY` (SEC1) into a `PRIVATE KEY` (PKCS#8) block.
+///
+/// The curve is taken from the key, or from the `EC PARAMETERS` block OpenSSL
+/// writes in front of keys that omit it. Like OpenSSL the curve is moved into
+/// the AlgorithmIdentifier and removed from the wrapped key.
+pub fn sec1_to_pkcs8(key: &Block, ec_parameters: Option<&Block>) -> Result<Block<'static>, PemParsingError> {
+    expect_block_type(key, "EC PRIVATE KEY")?;
+    let sec1 = Sec1::parse(&key.data)?;
+    let separate = match ec_parameters {
+        Some(block) => {
+            expect_block_type(block, "EC PARAMETERS")?;
+            Some(DerReader::new(&block.data).read_raw()?)
+        }
+        None => None,
+    };
+    let parameters = match (sec1.parameters, separate) {
+        (Some(a), Some(b)) if a != b => return Err(PemParsingError::DerError(String::from("EC parameters do not match the key"))),
+        (Some(p), _) | (None, Some(p)) => p,
+        (None, None) => return Err(PemParsingError::DerError(String::from("missing EC parameters"))),
+    };
+    let mut algorithm = tlv(TAG_OID, OID_EC_PUBLIC_KEY);
+    algorithm.extend_from_slice(parameters);
+    #[cfg_attr(not(feature = "zeroize"), allow(unused_mut))]
+    let mut inner = sec1.encode(None);
+    let ret = pkcs8(&algorithm, &inner);
+    #[cfg(feature = "zeroize")]
+    inner.zeroize();
+    Ok(private_key_block("PRIVATE KEY", ret))
+}
+
+/// Unwraps the `EC PRIVATE KEY` (SEC1) from a `PRIVATE KEY` (PKCS#8) block.
+///
+/// The curve of the AlgorithmIdentifier is written into the key, so no
+/// separate `EC PARAMETERS` block is needed.
+pub fn pkcs8_to_sec1(key: &Block) -> Result<Block<'static>, PemParsingError> {
+    expect_block_type(key, "PRIVATE KEY")?;
+    let (oid, parameters, private_key) = parse_pkcs8(&key.data)?;
+    if oid != OID_EC_PUBLIC_KEY {
+        return Err(PemParsingError::DerError(format!("not an EC key: {}", oid_name(oid))));
+    }
+    let sec1 = Sec1::parse(private_key)?;
+    Ok(private_key_block("EC PRIVATE KEY", sec1.encode(sec1.parameters.or(parameters))))
+}
@@ -0,0 +1,65 @@
+//! Certificate revocation list messages, see RFC 1424 section 5.
+//!
+//! A CRL message has the `Proc-Type: 4,CRL` header, followed by one `CRL` header per
+//! revocation list, each optionally followed by the certificate of the CRL issuer in an
+//! `Originator-Certificate` header and further `Issuer-Certificate` headers. There is no body.
+
+#[cfg(not(feature = "std"))]
+use alloc::{string::String, vec::Vec};
+use super::{Block, BodyEncoding, HeaderEntry, PemParsingError, ProcTypeType};
+
+/// a DER encoded CRL and the DER encoded certificate of its issuer
+pub type CrlEntry = (Vec<u8>, Option<Vec<u8>>);
+
+fn is_crl_message(block: &Block) -> bool {
+    matches!(block.headers.first(), Some(&HeaderEntry::ProcType(_, ProcTypeType::CRL)))
+}
+
+impl<'a> Block<'a> {
+    /// Returns the DER encoded CRLs of a `Proc-Type: 4,CRL` message, each with the DER encoded
+    /// certificate of its issuer if the message carries one.
+    ///
+    /// Blocks without the `Proc-Type: 4,CRL` header fail with `UnexpectedBlockType`, headers
+    /// out of order with `DerError`.
+    ///
+    /// The issuer certificate is the `Originator-Certificate` following the `CRL`,
+    /// or the first `Issuer-Certificate` if there is none.
+    pub fn crls(&self) -> Result<Vec<CrlEntry>, PemParsingError> {
+        if !is_crl_message(self) {
+            return Err(PemParsingError::UnexpectedBlockType(String::from(self.block_type)));
+        }
+        let mut crls: Vec<CrlEntry> = Vec::new();
+        for header in self.headers.iter().skip(1) {
+            let (cert, originator) = match *header {
+                HeaderEntry::CRL(ref crl) => {
+                    crls.push((crl.clone(), None));
+                    continue;
+                }
+                HeaderEntry::OriginatorCertificate(ref cert) => (cert, true),
+                HeaderEntry::IssuerCertificate(ref cert) => (cert, false),
+                _ => return Err(PemParsingError::DerError(format!("unexpected header {} in CRL message", header.fields().0))),
+            };
+            let issuer = match crls.last_mut() {
+                Some(&mut (_, ref mut issuer)) => issuer,
+                None => return Err(PemParsingError::DerError(format!("{} before the first CRL", header.fields().0))),
+            };
+            if originator || issuer.is_none() {
+                *issuer = Some(cert.clone());
+            }
+        }
+        Ok(crls)
+    }
+}
+
+/// Builds a `PRIVACY-ENHANCED MESSAGE` block with `Proc-Type: 4,CRL` from DER encoded CRLs,
+/// each with the optional DER encoded certificate of its issuer.
+pub fn crl_message(crls: &[(&[u8], Option<&[u8]>)]) -> Block<'static> {
+    let mut headers = vec![HeaderEntry::ProcType(4, ProcTypeType::CRL)];
+    for &(crl, issuer) in crls.iter() {
+        headers.push(HeaderEntry::CRL(crl.to_vec()));
+        if let Some(issuer) = issuer {
+            headers.push(HeaderEntry::OriginatorCertificate(issuer.to_vec()));
+        }
+    }
+    Block { block_type: "PRIVACY-ENHANCED MESSAGE", headers, data: Vec::new(), encoding: BodyEncoding::Base64, spans: None }
+}
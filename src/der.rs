@@ -0,0 +1,303 @@
+//! Minimal DER (X.690) reader used to look inside the data of PEM blocks.
+//! Only definite lengths and single byte tags are supported, which covers
+//! everything found in certificates and keys.
+
+#[cfg(not(feature = "std"))]
+use alloc::{string::String, vec::Vec};
+use super::PemParsingError;
+
+pub const TAG_BOOLEAN: u8 = 0x01;
+pub const TAG_INTEGER: u8 = 0x02;
+pub const TAG_BIT_STRING: u8 = 0x03;
+pub const TAG_OCTET_STRING: u8 = 0x04;
+pub const TAG_NULL: u8 = 0x05;
+pub const TAG_OID: u8 = 0x06;
+pub const TAG_UTF8_STRING: u8 = 0x0c;
+pub const TAG_PRINTABLE_STRING: u8 = 0x13;
+pub const TAG_T61_STRING: u8 = 0x14;
+pub const TAG_IA5_STRING: u8 = 0x16;
+pub const TAG_UTC_TIME: u8 = 0x17;
+pub const TAG_GENERALIZED_TIME: u8 = 0x18;
+pub const TAG_UNIVERSAL_STRING: u8 = 0x1c;
+pub const TAG_BMP_STRING: u8 = 0x1e;
+pub const TAG_SEQUENCE: u8 = 0x30;
+pub const TAG_SET: u8 = 0x31;
+
+/// context specific, constructed tag `[n]`
+pub fn tag_explicit(n: u8) -> u8 { 0xa0 | n }
+
+/// context specific, primitive tag `[n]`
+pub fn tag_implicit(n: u8) -> u8 { 0x80 | n }
+
+fn der_error(msg: &str) -> PemParsingError {
+    PemParsingError::DerError(String::from(msg))
+}
+
+/// splits one TLV from the input, returns the tag, the header length and the total length
+fn der_header(i: &[u8]) -> Result<(u8, usize, usize), PemParsingError> {
+    if i.len() < 2 {
+        return Err(der_error("truncated"));
+    }
+    let tag = i[0];
+    if tag & 0x1f == 0x1f {
+        return Err(der_error("high tag numbers are not supported"));
+    }
+    let first = i[1];
+    let (header, length) = if first < 0x80 {
+        (2, first as usize)
+    } else {
+        let n = (first & 0x7f) as usize;
+        if n == 0 || n > 4 {
+            return Err(der_error("unsupported length"));
+        }
+        if i.len() < 2 + n {
+            return Err(der_error("truncated"));
+        }
+        let length = i[2..2 + n].iter().fold(0usize, |a, &b| (a << 8) | b as usize);
+        (2 + n, length)
+    };
+    if i.len() - header < length {
+        return Err(der_error("truncated"));
+    }
+    Ok((tag, header, header + length))
+}
+
+/// sequential reader over the content of a constructed DER value
+#[derive(Debug, Clone)]
+pub struct DerReader<'a> {
+    input: &'a [u8],
+}
+
+impl<'a> DerReader<'a> {
+    pub fn new(input: &'a [u8]) -> DerReader<'a> {
+        DerReader { input }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.input.is_empty()
+    }
+
+    pub fn peek_tag(&self) -> Option<u8> {
+        self.input.first().cloned()
+    }
+
+    /// reads the next value, returns its tag and content
+    pub fn read_any(&mut self) -> Result<(u8, &'a [u8]), PemParsingError> {
+        let (tag, header, total) = der_header(self.input)?;
+        let value = &self.input[header..total];
+        // X.690 8.3.1, an INTEGER has at least one content octet
+        if tag == TAG_INTEGER && value.is_empty() {
+            return Err(der_error("empty INTEGER"));
+        }
+        self.input = &self.input[total..];
+        Ok((tag, value))
+    }
+
+    /// reads the next value including tag and length bytes
+    pub fn read_raw(&mut self) -> Result<&'a [u8], PemParsingError> {
+        let (_, _, total) = der_header(self.input)?;
+        let raw = &self.input[..total];
+        self.input = &self.input[total..];
+        Ok(raw)
+    }
+
+    /// reads the next value and checks its tag
+    pub fn read(&mut self, tag: u8) -> Result<&'a [u8], PemParsingError> {
+        match self.peek_tag() {
+            Some(t) if t == tag => self.read_any().map(|(_, value)| value),
+            Some(t) => Err(PemParsingError::DerError(format!("expected tag 0x{:02x}, found 0x{:02x}", tag, t))),
+            None => Err(der_error("truncated")),
+        }
+    }
+
+    /// reads the next value if it has the given tag
+    pub fn read_optional(&mut self, tag: u8) -> Result<Option<&'a [u8]>, PemParsingError> {
+        if self.peek_tag() == Some(tag) {
+            self.read(tag).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub fn read_sequence(&mut self) -> Result<DerReader<'a>, PemParsingError> {
+        self.read(TAG_SEQUENCE).map(DerReader::new)
+    }
+
+    /// reads a BIT STRING without unused bits
+    pub fn read_bit_string(&mut self) -> Result<&'a [u8], PemParsingError> {
+        bit_string(self.read(TAG_BIT_STRING)?)
+    }
+}
+
+/// appends the DER encoding of one value to `out`
+pub fn write_tlv(out: &mut Vec<u8>, tag: u8, value: &[u8]) {
+    out.push(tag);
+    let length = value.len();
+    if length < 0x80 {
+        out.push(length as u8);
+    } else {
+        let bytes = (0..4).rev().map(|i| (length >> (i * 8)) as u8).skip_while(|&b| b == 0).collect::<Vec<u8>>();
+        out.push(0x80 | bytes.len() as u8);
+        out.extend_from_slice(&bytes);
+    }
+    out.extend_from_slice(value);
+}
+
+/// DER encoding of one value
+pub fn tlv(tag: u8, value: &[u8]) -> Vec<u8> {
+    let mut ret = Vec::with_capacity(value.len() + 6);
+    write_tlv(&mut ret, tag, value);
+    ret
+}
+
+/// appends an unsigned big endian integer as DER INTEGER
+pub fn write_unsigned_integer(out: &mut Vec<u8>, value: &[u8]) {
+    let value = unsigned_integer(value);
+    if value.first().map(|&b| b & 0x80 != 0).unwrap_or(false) {
+        let mut padded = vec![0u8];
+        padded.extend_from_slice(value);
+        write_tlv(out, TAG_INTEGER, &padded);
+    } else {
+        write_tlv(out, TAG_INTEGER, value);
+    }
+}
+
+/// content of a BIT STRING without unused bits
+pub fn bit_string(value: &[u8]) -> Result<&[u8], PemParsingError> {
+    match value.first() {
+        Some(&0) => Ok(&value[1..]),
+        _ => Err(der_error("unsupported bit string")),
+    }
+}
+
+/// number of significant bits of an unsigned big endian integer
+pub fn bit_length(value: &[u8]) -> usize {
+    let value = unsigned_integer(value);
+    match value.first() {
+        Some(&b) if b != 0 => (value.len() - 1) * 8 + (8 - b.leading_zeros() as usize),
+        _ => 0,
+    }
+}
+
+/// formats an OBJECT IDENTIFIER in dotted notation
+pub fn oid_to_string(oid: &[u8]) -> String {
+    let mut ret = String::new();
+    let mut register = 0u64;
+    let mut first = true;
+    for &b in oid.iter() {
+        register = (register << 7) | (b & 0x7f) as u64;
+        if b & 0x80 != 0 {
+            continue;
+        }
+        if first {
+            let (x, y) = if register < 80 { (register / 40, register % 40) } else { (2, register - 80) };
+            ret.push_str(&format!("{}.{}", x, y));
+            first = false;
+        } else {
+            ret.push_str(&format!(".{}", register));
+        }
+        register = 0;
+    }
+    ret
+}
+
+/// strips the leading zero of a positive INTEGER
+pub fn unsigned_integer(value: &[u8]) -> &[u8] {
+    let mut pos = 0;
+    while pos + 1 < value.len() && value[pos] == 0 {
+        pos += 1;
+    }
+    &value[pos..]
+}
+
+// well known object identifiers, content octets only
+
+pub const OID_RSA_ENCRYPTION: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x01];
+pub const OID_RSASSA_PSS: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x0a];
+pub const OID_DSA: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x38, 0x04, 0x01];
+pub const OID_DH_KEY_AGREEMENT: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x03, 0x01];
+pub const OID_DH_PUBLIC_NUMBER: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3e, 0x02, 0x01];
+pub const OID_EC_PUBLIC_KEY: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02, 0x01];
+pub const OID_X25519: &[u8] = &[0x2b, 0x65, 0x6e];
+pub const OID_X448: &[u8] = &[0x2b, 0x65, 0x6f];
+pub const OID_ED25519: &[u8] = &[0x2b, 0x65, 0x70];
+pub const OID_ED448: &[u8] = &[0x2b, 0x65, 0x71];
+pub const OID_SECP256R1: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07];
+pub const OID_SECP384R1: &[u8] = &[0x2b, 0x81, 0x04, 0x00, 0x22];
+pub const OID_SECP521R1: &[u8] = &[0x2b, 0x81, 0x04, 0x00, 0x23];
+pub const OID_SUBJECT_KEY_IDENTIFIER: &[u8] = &[0x55, 0x1d, 0x0e];
+pub const OID_SUBJECT_ALT_NAME: &[u8] = &[0x55, 0x1d, 0x11];
+pub const OID_BASIC_CONSTRAINTS: &[u8] = &[0x55, 0x1d, 0x13];
+pub const OID_AUTHORITY_KEY_IDENTIFIER: &[u8] = &[0x55, 0x1d, 0x23];
+
+static OID_NAMES: &[(&[u8], &str)] = &[
+    (&[0x55, 0x04, 0x03], "CN"),
+    (&[0x55, 0x04, 0x04], "SN"),
+    (&[0x55, 0x04, 0x05], "serialNumber"),
+    (&[0x55, 0x04, 0x06], "C"),
+    (&[0x55, 0x04, 0x07], "L"),
+    (&[0x55, 0x04, 0x08], "ST"),
+    (&[0x55, 0x04, 0x09], "street"),
+    (&[0x55, 0x04, 0x0a], "O"),
+    (&[0x55, 0x04, 0x0b], "OU"),
+    (&[0x55, 0x04, 0x0c], "title"),
+    (&[0x55, 0x04, 0x2a], "GN"),
+    (&[0x09, 0x92, 0x26, 0x89, 0x93, 0xf2, 0x2c, 0x64, 0x01, 0x01], "UID"),
+    (&[0x09, 0x92, 0x26, 0x89, 0x93, 0xf2, 0x2c, 0x64, 0x01, 0x19], "DC"),
+    (&[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x09, 0x01], "emailAddress"),
+    (OID_RSA_ENCRYPTION, "rsaEncryption"),
+    (OID_RSASSA_PSS, "RSASSA-PSS"),
+    (OID_DSA, "dsaEncryption"),
+    (OID_DH_KEY_AGREEMENT, "dhKeyAgreement"),
+    (OID_DH_PUBLIC_NUMBER, "dhpublicnumber"),
+    (OID_EC_PUBLIC_KEY, "id-ecPublicKey"),
+    (OID_ED25519, "ED25519"),
+    (OID_ED448, "ED448"),
+    (OID_X25519, "X25519"),
+    (OID_X448, "X448"),
+];
+
+/// short name of a well known OID, the dotted notation otherwise
+pub fn oid_name(oid: &[u8]) -> String {
+    for &(known, name) in OID_NAMES.iter() {
+        if known == oid {
+            return String::from(name);
+        }
+    }
+    oid_to_string(oid)
+}
+
+#[cfg(test)]
+#[test]
+fn test_der_reader() {
+    let b = [0x30u8, 0x81, 0x05, 0x02, 0x01, 0x05, 0x05, 0x00, 0x01, 0x01, 0xff];
+    let mut reader = DerReader::new(&b);
+    let mut seq = reader.read_sequence().unwrap();
+    assert_eq!(&[0x01u8, 0x01, 0xff][..], reader.read_raw().unwrap());
+    assert!(reader.is_empty());
+    assert_eq!(&[5u8][..], seq.read(TAG_INTEGER).unwrap());
+    assert_eq!(None, seq.read_optional(TAG_BOOLEAN).unwrap());
+    assert_eq!((TAG_NULL, &[][..]), seq.read_any().unwrap());
+    assert!(seq.is_empty());
+    assert!(DerReader::new(&[0x30u8, 0x05, 0x00]).read_sequence().is_err());
+    assert!(DerReader::new(&[0x02u8, 0x00]).read(TAG_INTEGER).is_err());
+
+    let long = vec![0u8; 300];
+    let encoded = tlv(TAG_OCTET_STRING, &long);
+    assert_eq!(&[0x04u8, 0x82, 0x01, 0x2c][..], &encoded[..4]);
+    assert_eq!(&long[..], DerReader::new(&encoded).read(TAG_OCTET_STRING).unwrap());
+    assert_eq!(vec![0x05u8, 0x00], tlv(TAG_NULL, &[]));
+}
+
+#[cfg(test)]
+#[test]
+fn test_oid() {
+    assert_eq!("1.2.840.113549.1.1.1", oid_to_string(OID_RSA_ENCRYPTION));
+    assert_eq!("2.5.29.17", oid_to_string(OID_SUBJECT_ALT_NAME));
+    assert_eq!("CN", oid_name(&[0x55, 0x04, 0x03]));
+    assert_eq!("1.3.6.1", oid_name(&[0x2b, 0x06, 0x01]));
+    assert_eq!(&[0x80u8, 0x01][..], unsigned_integer(&[0x00, 0x80, 0x01]));
+    assert_eq!(9, bit_length(&[0x00, 0x01, 0xff]));
+    assert_eq!(2048, bit_length(&[0x80; 256]));
+    assert_eq!(0, bit_length(&[0x00]));
+}
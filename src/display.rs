@@ -1,20 +1,24 @@
-#[cfg(not(std))]
+#[cfg(not(feature = "std"))]
 use core::fmt;
-#[cfg(std)]
+#[cfg(feature = "std")]
 use std::fmt;
+#[cfg(not(feature = "std"))]
+use alloc::{string::String, vec::Vec};
 use super::HeaderEntry;
 
 pub fn write_headers(f: &mut fmt::Formatter, headers: &Vec<HeaderEntry>) -> fmt::Result {
-    if headers.len() > 0 {
+    if !headers.is_empty() {
         for header in headers.iter() {
-            write!(f, "{}\n", header)?;
+            writeln!(f, "{}", header)?;
         }
-        write!(f, "\n")
+        writeln!(f)
     } else {
         Ok(())
     }
 }
 
+/// writes `data` base64 encoded in lines of `width` characters,
+/// a width of 0 writes a single line without line ending
 pub fn write_base64(f: &mut fmt::Formatter, data: &[u8], width: usize) -> fmt::Result {
     let mut pos = 0;
     let mut register = 0usize;
@@ -32,7 +36,7 @@ pub fn write_base64(f: &mut fmt::Formatter, data: &[u8], width: usize) -> fmt::R
             write!(f, "{}", c)?;
             out += 1;
             if out == width {
-                write!(f, "\n")?;
+                writeln!(f)?;
                 out = 0;
             }
             bits -= 6;
@@ -49,13 +53,40 @@ pub fn write_base64(f: &mut fmt::Formatter, data: &[u8], width: usize) -> fmt::R
             out += 1;
         }
     }
-    if out > 0 {
-        write!(f, "\n")
+    if out > 0 && width > 0 {
+        writeln!(f)
     } else {
         Ok(())
     }
 }
 
-pub const STANDARD_ENCODE: &'static [u8; 64] = &[
+struct Base64<'a>(&'a [u8]);
+
+impl<'a> fmt::Display for Base64<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_base64(f, self.0, 0)
+    }
+}
+
+/// `data` base64 encoded in a single line
+pub fn base64_string(data: &[u8]) -> String {
+    format!("{}", Base64(data))
+}
+
+/// appends the text of a MIC-CLEAR message to `out`, lines starting with a dash are escaped with `- `
+pub fn write_cleartext(out: &mut Vec<u8>, text: &[u8]) {
+    if text.is_empty() {
+        return;
+    }
+    for line in text.split(|&c| c == b'\n') {
+        if line.first() == Some(&b'-') {
+            out.extend_from_slice(b"- ");
+        }
+        out.extend_from_slice(line);
+        out.push(b'\n');
+    }
+}
+
+pub const STANDARD_ENCODE: &[u8; 64] = &[
     65, 66, 67, 68, 69, 70, 71, 72, 73, 74, 75, 76, 77, 78, 79, 80, 81, 82, 83, 84, 85, 86, 87, 88, 89, 90, 97, 98, 99, 100, 101, 102, 103, 104, 105, 106, 107, 108, 109, 110, 111, 112, 113, 114, 115, 116, 117, 118, 119, 120, 121, 122, 48, 49, 50, 51, 52, 53, 54, 55, 56, 57, 43, 47, // input 63 (0x3F) => '/' (0x2F)
 ];
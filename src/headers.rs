@@ -1,22 +1,75 @@
-#[cfg(not(std))]
+#[cfg(not(feature = "std"))]
 use core::str;
-#[cfg(std)]
+#[cfg(feature = "std")]
 use std::str;
-#[cfg(not(std))]
+#[cfg(not(feature = "std"))]
 use core::fmt;
-#[cfg(std)]
+#[cfg(feature = "std")]
 use std::fmt;
 
 
+#[cfg(not(feature = "std"))]
+use alloc::{string::String, vec::Vec};
 use nom::{IResult, Err, Needed, is_space};
+use super::base64::base64;
+use super::display::base64_string;
 
 #[derive(Debug, PartialEq)]
 pub enum HeaderEntry<'a> {
     ProcType(u8, ProcTypeType),
     DEKInfo(RFC1423Algorithm, Vec<u8>),
+    /// `Content-Domain`, e.g. `RFC822`
+    ContentDomain(String),
+    OriginatorIDSymmetric(SymmetricID),
+    OriginatorIDAsymmetric(AsymmetricID),
+    RecipientIDSymmetric(SymmetricID),
+    RecipientIDAsymmetric(AsymmetricID),
+    /// `Originator-Certificate` with the DER encoded certificate
+    OriginatorCertificate(Vec<u8>),
+    /// `Issuer-Certificate` with the DER encoded certificate
+    IssuerCertificate(Vec<u8>),
+    KeyInfo(RFC1421KeyInfo),
+    MICInfo(MICInfo),
+    /// `CRL` with the DER encoded certificate revocation list
+    CRL(Vec<u8>),
     Entry(&'a str, Vec<String>),
 }
 
+/// the fields of `Originator-ID-Symmetric` and `Recipient-ID-Symmetric`, see RFC 1421 section 4.6.2
+#[derive(Debug, PartialEq, Clone)]
+pub struct SymmetricID {
+    pub entity_identifier: String,
+    pub issuing_authority: String,
+    pub version: String,
+}
+
+/// the fields of `Originator-ID-Asymmetric` and `Recipient-ID-Asymmetric`, see RFC 1421 section 4.6.2
+#[derive(Debug, PartialEq, Clone)]
+pub struct AsymmetricID {
+    /// DER encoded name of the certificate issuer
+    pub issuer: Vec<u8>,
+    /// serial number of the certificate
+    pub serial_number: Vec<u8>,
+}
+
+/// the fields of `Key-Info`, see RFC 1421 section 4.6.4
+#[derive(Debug, PartialEq, Clone)]
+pub enum RFC1421KeyInfo {
+    /// `Key-Info: <IK use algorithm>,<MIC algorithm>,<DEK>,<MIC>` with hex encoded values
+    Symmetric { ik_use_algorithm: String, mic_algorithm: String, dek: Vec<u8>, mic: Vec<u8> },
+    /// `Key-Info: <IK use algorithm>,<DEK>` with the base64 encoded DEK
+    Asymmetric { ik_use_algorithm: String, dek: Vec<u8> },
+}
+
+/// the fields of `MIC-Info`, see RFC 1421 section 4.6.3
+#[derive(Debug, PartialEq, Clone)]
+pub struct MICInfo {
+    pub mic_algorithm: String,
+    pub ik_use_algorithm: String,
+    /// the signed MIC
+    pub mic: Vec<u8>,
+}
+
 #[repr(u8)]
 #[derive(Debug, PartialEq)]
 #[allow(non_camel_case_types)]
@@ -67,7 +120,7 @@ impl RFC1423Algorithm {
     }
 }
 
-pub fn is_pem_header_key_char(c: u8) -> bool { (c >= 0x41 && c <= 0x5A) || (c >= 0x61 && c <= 0x7A) || c == 45 }
+pub fn is_pem_header_key_char(c: u8) -> bool { (0x41..=0x5A).contains(&c) || (0x61..=0x7A).contains(&c) || c == 45 }
 named!(pub pem_header_key<&str>,map_res!(take_while!(is_pem_header_key_char),str::from_utf8));
 
 named!(pub pem_header_value<Vec<String>>,  do_parse!(
@@ -127,32 +180,177 @@ named!(pub pem_header_key_value<HeaderEntry>,  do_parse!(
     tag!(":") >>
     spaces >>
     values: pem_header_value >>
-    (HeaderEntry::Entry(key, values))
+    (typed_entry(key, values))
     ));
 
-named!(pub pem_headers<Vec<HeaderEntry>>, do_parse!(
-    headers: many1!(pem_header) >>
-    tag!("\n") >>
+fn decode_base64(s: &str) -> Option<Vec<u8>> {
+    match base64(s.as_bytes()) {
+        Ok((&[], data)) => Some(data),
+        _ => None,
+    }
+}
+
+fn decode_hex(s: &str) -> Option<Vec<u8>> {
+    let padded = if s.len() % 2 == 1 { format!("0{}", s) } else { String::from(s) };
+    match parse_hex(padded.as_bytes()) {
+        Ok((&[], data)) => Some(data),
+        _ => None,
+    }
+}
+
+/// maps the comma separated fields of the RFC 1421 headers to their typed variant,
+/// `Proc-Type` and `DEK-Info` are parsed by `pem_header` before, but are mapped here
+/// for headers built from their fields
+fn typed_value(key: &str, values: &[String]) -> Option<HeaderEntry<'static>> {
+    let fields: Vec<&str> = values.iter().map(|v| v.trim()).collect();
+    let symmetric_id = || match fields[..] {
+        [entity, authority, version] => Some(SymmetricID {
+            entity_identifier: String::from(entity),
+            issuing_authority: String::from(authority),
+            version: String::from(version),
+        }),
+        _ => None,
+    };
+    let asymmetric_id = || match fields[..] {
+        [issuer, serial] => Some(AsymmetricID { issuer: decode_base64(issuer)?, serial_number: decode_hex(serial)? }),
+        _ => None,
+    };
+    match (key, &fields[..]) {
+        ("Proc-Type", &[version, t]) => match pem_proctype(t.as_bytes()) {
+            Ok((&[], t)) => Some(HeaderEntry::ProcType(version.parse().ok()?, t)),
+            _ => None,
+        },
+        ("DEK-Info", &[alg, iv]) => match pem_rfc1423_algorithm(alg.as_bytes()) {
+            Ok((&[], alg)) => Some(HeaderEntry::DEKInfo(alg, decode_hex(iv)?)),
+            _ => None,
+        },
+        ("Content-Domain", &[domain]) => Some(HeaderEntry::ContentDomain(String::from(domain))),
+        ("Originator-ID-Symmetric", _) => symmetric_id().map(HeaderEntry::OriginatorIDSymmetric),
+        ("Recipient-ID-Symmetric", _) => symmetric_id().map(HeaderEntry::RecipientIDSymmetric),
+        ("Originator-ID-Asymmetric", _) => asymmetric_id().map(HeaderEntry::OriginatorIDAsymmetric),
+        ("Recipient-ID-Asymmetric", _) => asymmetric_id().map(HeaderEntry::RecipientIDAsymmetric),
+        ("Originator-Certificate", &[cert]) => decode_base64(cert).map(HeaderEntry::OriginatorCertificate),
+        ("Issuer-Certificate", &[cert]) => decode_base64(cert).map(HeaderEntry::IssuerCertificate),
+        ("CRL", &[crl]) => decode_base64(crl).map(HeaderEntry::CRL),
+        ("Key-Info", &[ik_use, mic_alg, dek, mic]) => Some(HeaderEntry::KeyInfo(RFC1421KeyInfo::Symmetric {
+            ik_use_algorithm: String::from(ik_use),
+            mic_algorithm: String::from(mic_alg),
+            dek: decode_hex(dek)?,
+            mic: decode_hex(mic)?,
+        })),
+        ("Key-Info", &[ik_use, dek]) => Some(HeaderEntry::KeyInfo(RFC1421KeyInfo::Asymmetric {
+            ik_use_algorithm: String::from(ik_use),
+            dek: decode_base64(dek)?,
+        })),
+        ("MIC-Info", &[mic_alg, ik_use, mic]) => Some(HeaderEntry::MICInfo(MICInfo {
+            mic_algorithm: String::from(mic_alg),
+            ik_use_algorithm: String::from(ik_use),
+            mic: decode_base64(mic)?,
+        })),
+        _ => None,
+    }
+}
+
+/// the typed variant for known RFC 1421 headers, `HeaderEntry::Entry` for all others
+/// and for values that do not match the expected fields
+pub fn typed_entry(key: &str, values: Vec<String>) -> HeaderEntry<'_> {
+    match typed_value(key, &values) {
+        Some(entry) => entry,
+        None => HeaderEntry::Entry(key, values),
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_typed_entry() {
+    let fields = |s: &str| s.split(',').map(String::from).collect::<Vec<String>>();
+    assert_eq!(HeaderEntry::OriginatorIDSymmetric(SymmetricID {
+        entity_identifier: String::from("linn@zendia.enet.dec.com"),
+        issuing_authority: String::new(),
+        version: String::new(),
+    }), typed_entry("Originator-ID-Symmetric", fields("linn@zendia.enet.dec.com,,")));
+    assert_eq!(HeaderEntry::RecipientIDAsymmetric(AsymmetricID { issuer: vec![0x30, 0x00], serial_number: vec![0x01, 0x02] }),
+               typed_entry("Recipient-ID-Asymmetric", fields("MAA=,102")));
+    assert_eq!(HeaderEntry::Entry("Key-Info", fields("DES-ECB,RSA-MD2,XYZ,00")),
+               typed_entry("Key-Info", fields("DES-ECB,RSA-MD2,XYZ,00")));
+}
+
+impl<'a> HeaderEntry<'a> {
+    /// the header name and its comma separated values as written in a message
+    pub fn fields(&self) -> (&str, Vec<String>) {
+        match *self {
+            HeaderEntry::ProcType(ref l, ref t) => ("Proc-Type", vec![format!("{}", l), format!("{}", t)]),
+            HeaderEntry::DEKInfo(ref alg, ref iv) => ("DEK-Info", vec![format!("{}", alg), hex_string(iv)]),
+            HeaderEntry::ContentDomain(ref domain) => ("Content-Domain", vec![domain.clone()]),
+            HeaderEntry::OriginatorIDSymmetric(ref id) => ("Originator-ID-Symmetric", id.fields()),
+            HeaderEntry::RecipientIDSymmetric(ref id) => ("Recipient-ID-Symmetric", id.fields()),
+            HeaderEntry::OriginatorIDAsymmetric(ref id) => ("Originator-ID-Asymmetric", id.fields()),
+            HeaderEntry::RecipientIDAsymmetric(ref id) => ("Recipient-ID-Asymmetric", id.fields()),
+            HeaderEntry::OriginatorCertificate(ref cert) => ("Originator-Certificate", vec![base64_string(cert)]),
+            HeaderEntry::IssuerCertificate(ref cert) => ("Issuer-Certificate", vec![base64_string(cert)]),
+            HeaderEntry::KeyInfo(RFC1421KeyInfo::Symmetric { ref ik_use_algorithm, ref mic_algorithm, ref dek, ref mic }) => {
+                ("Key-Info", vec![ik_use_algorithm.clone(), mic_algorithm.clone(), hex_string(dek), hex_string(mic)])
+            }
+            HeaderEntry::KeyInfo(RFC1421KeyInfo::Asymmetric { ref ik_use_algorithm, ref dek }) => {
+                ("Key-Info", vec![ik_use_algorithm.clone(), base64_string(dek)])
+            }
+            HeaderEntry::MICInfo(ref info) => {
+                ("MIC-Info", vec![info.mic_algorithm.clone(), info.ik_use_algorithm.clone(), base64_string(&info.mic)])
+            }
+            HeaderEntry::CRL(ref crl) => ("CRL", vec![base64_string(crl)]),
+            HeaderEntry::Entry(key, ref values) => (key, values.clone()),
+        }
+    }
+}
+
+impl SymmetricID {
+    fn fields(&self) -> Vec<String> {
+        vec![self.entity_identifier.clone(), self.issuing_authority.clone(), self.version.clone()]
+    }
+}
+
+impl AsymmetricID {
+    fn fields(&self) -> Vec<String> {
+        vec![base64_string(&self.issuer), hex_string(&self.serial_number)]
+    }
+}
+
+/// a header and the length of the input left after it, from which its position is derived
+fn pem_header_rest_len(i: &[u8]) -> IResult<&[u8], (HeaderEntry<'_>, usize)> {
+    let (rest, header) = pem_header(i)?;
+    Ok((rest, (header, rest.len())))
+}
+
+// the empty line may be missing if the message has no body, like RFC 1424 CRL messages
+named!(pub pem_headers_rest_lens<(Vec<HeaderEntry>, Vec<usize>)>, do_parse!(
+    headers: fold_many1!(pem_header_rest_len, (Vec::new(), Vec::new()), |(mut headers, mut rest_lens): (Vec<_>, Vec<_>), (header, rest_len)| {
+        headers.push(header);
+        rest_lens.push(rest_len);
+        (headers, rest_lens)
+    }) >>
+    alt!(tag!("\n") | peek!(tag!("-----END "))) >>
     (headers)
     ));
 
+named!(pub pem_headers<Vec<HeaderEntry>>, map!(pem_headers_rest_lens, |(headers, _)| headers));
+
 pub fn parse_hex(i: &[u8]) -> ::nom::IResult<&[u8], Vec<u8>> {
     let mut high = true;
     let mut register = 0u8;
     let mut ret: Vec<u8> = Vec::new();
     let mut pos = 0;
-    const T1: u8 = ('1' as u8) - 1;
-    const T2: u8 = ('A' as u8) - 1;
-    const T3: u8 = ('a' as u8) - 1;
+    const T1: u8 = b'1' - 1;
+    const T2: u8 = b'A' - 1;
+    const T3: u8 = b'a' - 1;
     while pos < i.len() {
         let c = i[pos];
-        let b: u8 = if c == ('0' as u8) {
+        let b: u8 = if c == b'0' {
             0
-        } else if (c > T1) & (c <= ('9' as u8)) {
+        } else if (c > T1) & (c <= b'9') {
             c - T1
-        } else if (c > T2) & (c <= ('F' as u8)) {
+        } else if (c > T2) & (c <= b'F') {
             c - T2 + 9
-        } else if (c > T3) & (c <= ('f' as u8)) {
+        } else if (c > T3) & (c <= b'f') {
             c - T3 + 9
         } else {
             break;
@@ -167,9 +365,9 @@ pub fn parse_hex(i: &[u8]) -> ::nom::IResult<&[u8], Vec<u8>> {
         pos += 1;
     }
     if high {
-        return Ok((&i[pos..], ret));
+        Ok((&i[pos..], ret))
     } else {
-        return Err(Err::Incomplete(Needed::Size(1)));
+        Err(Err::Incomplete(Needed::Size(1)))
     }
 }
 
@@ -185,15 +383,27 @@ impl fmt::Display for RFC1423Algorithm {
     }
 }
 
+impl fmt::Display for ProcTypeType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ProcTypeType::ENCRYPTED => write!(f, "ENCRYPTED"),
+            ProcTypeType::MIC_ONLY => write!(f, "MIC-ONLY"),
+            ProcTypeType::MIC_CLEAR => write!(f, "MIC-CLEAR"),
+            ProcTypeType::CRL => write!(f, "CRL")
+        }
+    }
+}
+
 impl<'a> fmt::Display for HeaderEntry<'a> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self {
-            &HeaderEntry::ProcType(ref l, ref t) => { write!(f, "Proc-Type: {},{:?}", l, t) }
-            &HeaderEntry::DEKInfo(ref alg, ref v) => {
+        match *self {
+            HeaderEntry::ProcType(ref l, ref t) => { write!(f, "Proc-Type: {},{}", l, t) }
+            HeaderEntry::DEKInfo(ref alg, ref v) => {
                 write!(f, "DEK-Info: {},", alg)?;
-                write_hex(f, &v)
+                write_hex(f, v)
             }
-            &HeaderEntry::Entry(ref key, ref values) => {
+            _ => {
+                let (key, values) = self.fields();
                 write!(f, "{}: ", key)?;
                 let mut pos: usize = key.len() + 2;
                 for (i, v) in values.iter().enumerate() {
@@ -220,6 +430,10 @@ impl<'a> fmt::Display for HeaderEntry<'a> {
     }
 }
 
+fn hex_string(data: &[u8]) -> String {
+    data.iter().map(|b| format!("{:02X}", b)).collect()
+}
+
 fn write_hex(f: &mut fmt::Formatter, data: &[u8]) -> fmt::Result {
     for i in data.iter() {
         write_hex_char(f, i >> 4)?;
@@ -229,7 +443,7 @@ fn write_hex(f: &mut fmt::Formatter, data: &[u8]) -> fmt::Result {
 }
 
 fn write_hex_char(f: &mut fmt::Formatter, b: u8) -> fmt::Result {
-    const T2: u8 = ('A' as u8) - 10;
+    const T2: u8 = b'A' - 10;
     if b > 9 {
         write!(f, "{}", (b + T2) as char)
     } else {
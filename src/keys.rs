@@ -0,0 +1,347 @@
+//! Public key material of private key and certificate blocks.
+
+#[cfg(not(feature = "std"))]
+use core::ptr;
+#[cfg(feature = "std")]
+use std::ptr;
+
+#[cfg(not(feature = "std"))]
+use alloc::{string::String, vec::Vec};
+use super::{Block, HeaderEntry, ProcTypeType, PemParsingError};
+use super::der::*;
+use super::x509::{parse_certificate, CERTIFICATE_LABELS};
+
+/// labels of the private key containers supported by this crate
+pub static PRIVATE_KEY_LABELS: &[&str] = &["PRIVATE KEY", "RSA PRIVATE KEY", "EC PRIVATE KEY"];
+
+/// the public half of a key pair, in a form comparable between keys and certificates
+#[derive(Debug, PartialEq)]
+enum PublicKeyId<'a> {
+    Rsa { modulus: &'a [u8], exponent: &'a [u8] },
+    /// x coordinate and parity of y, so compressed and uncompressed points compare equal
+    Ec { x: &'a [u8], y_odd: bool },
+    Other { algorithm: &'a [u8], key: &'a [u8] },
+}
+
+fn rsa_public_key(der: &[u8]) -> Result<PublicKeyId<'_>, PemParsingError> {
+    let mut key = DerReader::new(der).read_sequence()?;
+    let modulus = unsigned_integer(key.read(TAG_INTEGER)?);
+    let exponent = unsigned_integer(key.read(TAG_INTEGER)?);
+    Ok(PublicKeyId::Rsa { modulus, exponent })
+}
+
+fn rsa_private_key(der: &[u8]) -> Result<PublicKeyId<'_>, PemParsingError> {
+    let mut key = DerReader::new(der).read_sequence()?;
+    key.read(TAG_INTEGER)?; // version
+    let modulus = unsigned_integer(key.read(TAG_INTEGER)?);
+    let exponent = unsigned_integer(key.read(TAG_INTEGER)?);
+    Ok(PublicKeyId::Rsa { modulus, exponent })
+}
+
+fn ec_point(point: &[u8]) -> Result<PublicKeyId<'_>, PemParsingError> {
+    match point.first() {
+        Some(&4) if point.len() % 2 == 1 => Ok(PublicKeyId::Ec {
+            x: &point[1..1 + point.len() / 2],
+            y_odd: point[point.len() - 1] & 1 == 1,
+        }),
+        Some(&b) if b == 2 || b == 3 => Ok(PublicKeyId::Ec { x: &point[1..], y_odd: b == 3 }),
+        _ => Err(PemParsingError::DerError(String::from("invalid EC point"))),
+    }
+}
+
+/// public key of a SEC1 ECPrivateKey, if it is included
+fn ec_private_key(der: &[u8]) -> Result<Option<PublicKeyId<'_>>, PemParsingError> {
+    let mut key = DerReader::new(der).read_sequence()?;
+    key.read(TAG_INTEGER)?; // version
+    key.read(TAG_OCTET_STRING)?;
+    key.read_optional(tag_explicit(0))?;
+    match key.read_optional(tag_explicit(1))? {
+        Some(public_key) => ec_point(DerReader::new(public_key).read_bit_string()?).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// public key of a PKCS#8 PrivateKeyInfo or OneAsymmetricKey, if it can be derived without arithmetic
+fn pkcs8_private_key(der: &[u8]) -> Result<Option<PublicKeyId<'_>>, PemParsingError> {
+    let mut info = DerReader::new(der).read_sequence()?;
+    info.read(TAG_INTEGER)?; // version
+    let mut algorithm = info.read_sequence()?;
+    let oid = algorithm.read(TAG_OID)?;
+    let private_key = info.read(TAG_OCTET_STRING)?;
+    info.read_optional(tag_explicit(0))?; // attributes
+    let public_key = match info.read_optional(tag_implicit(1))? {
+        Some(bits) => Some(bit_string(bits)?),
+        None => None,
+    };
+    if oid == OID_RSA_ENCRYPTION || oid == OID_RSASSA_PSS {
+        return rsa_private_key(private_key).map(Some);
+    }
+    if oid == OID_EC_PUBLIC_KEY {
+        if let Some(point) = ec_private_key(private_key)? {
+            return Ok(Some(point));
+        }
+        return match public_key {
+            Some(point) => ec_point(point).map(Some),
+            None => Ok(None),
+        };
+    }
+    Ok(public_key.map(|key| PublicKeyId::Other { algorithm: oid, key }))
+}
+
+/// algorithm OID, raw algorithm parameters and private key of a PrivateKeyInfo
+pub type PrivateKeyInfo<'a> = (&'a [u8], Option<&'a [u8]>, &'a [u8]);
+
+pub fn parse_pkcs8(der: &[u8]) -> Result<PrivateKeyInfo<'_>, PemParsingError> {
+    let mut info = DerReader::new(der).read_sequence()?;
+    info.read(TAG_INTEGER)?;
+    let mut algorithm = info.read_sequence()?;
+    let oid = algorithm.read(TAG_OID)?;
+    let parameters = if algorithm.is_empty() { None } else { Some(algorithm.read_raw()?) };
+    let private_key = info.read(TAG_OCTET_STRING)?;
+    Ok((oid, parameters, private_key))
+}
+
+fn spki_public_key(spki: &[u8]) -> Result<PublicKeyId<'_>, PemParsingError> {
+    let mut spki = DerReader::new(spki).read_sequence()?;
+    let mut algorithm = spki.read_sequence()?;
+    let oid = algorithm.read(TAG_OID)?;
+    let key = spki.read_bit_string()?;
+    if oid == OID_RSA_ENCRYPTION || oid == OID_RSASSA_PSS {
+        rsa_public_key(key)
+    } else if oid == OID_EC_PUBLIC_KEY {
+        ec_point(key)
+    } else {
+        Ok(PublicKeyId::Other { algorithm: oid, key })
+    }
+}
+
+/// whether the block has a `Proc-Type: 4,ENCRYPTED` header
+pub fn is_encrypted(block: &Block) -> bool {
+    block.headers.iter().any(|h| matches!(*h, HeaderEntry::ProcType(_, ProcTypeType::ENCRYPTED)))
+}
+
+/// public key of a private key block, `None` if it is encrypted or cannot be derived from the DER
+fn private_key_public_key<'b>(block: &'b Block) -> Result<Option<PublicKeyId<'b>>, PemParsingError> {
+    if is_encrypted(block) {
+        return Ok(None);
+    }
+    match block.block_type {
+        "RSA PRIVATE KEY" => rsa_private_key(&block.data).map(Some),
+        "EC PRIVATE KEY" => ec_private_key(&block.data),
+        _ => pkcs8_private_key(&block.data),
+    }
+}
+
+/// private keys of a bundle paired with their certificates
+#[derive(Debug)]
+pub struct KeyMatches<'b, 'a: 'b> {
+    /// pairs of private key and certificate
+    pub pairs: Vec<(&'b Block<'a>, &'b Block<'a>)>,
+    /// private keys without certificate
+    pub unmatched_keys: Vec<&'b Block<'a>>,
+    /// private keys that cannot be matched because they are encrypted or their
+    /// public half is neither stored in the DER nor derivable without curve arithmetic,
+    /// e.g. PKCS#8 v1 Ed25519 keys and SEC1 keys without the optional public key
+    pub undetermined_keys: Vec<&'b Block<'a>>,
+    /// certificates without private key
+    pub unmatched_certificates: Vec<&'b Block<'a>>,
+    /// certificates and private keys whose DER cannot be parsed
+    pub invalid_blocks: Vec<(&'b Block<'a>, PemParsingError)>,
+}
+
+/// Pairs each private key block with the certificate holding its public key.
+///
+/// RSA keys are compared by modulus and exponent, EC keys by their public point.
+/// Other algorithms only match if the key carries its public key (PKCS#8 v2),
+/// keys without it are reported in `undetermined_keys` instead of `unmatched_keys`.
+/// Blocks that fail to parse are reported in `invalid_blocks`.
+pub fn match_keys<'b, 'a>(blocks: &'b [Block<'a>]) -> KeyMatches<'b, 'a> {
+    let mut certificates = Vec::new();
+    let mut invalid_blocks = Vec::new();
+    for block in blocks.iter().filter(|b| CERTIFICATE_LABELS.contains(&b.block_type)) {
+        match parse_certificate(&block.data).and_then(|cert| spki_public_key(cert.subject_public_key_info)) {
+            Ok(public_key) => certificates.push((block, public_key)),
+            Err(e) => invalid_blocks.push((block, e)),
+        }
+    }
+    let mut pairs = Vec::new();
+    let mut unmatched_keys = Vec::new();
+    let mut undetermined_keys = Vec::new();
+    for key in blocks.iter().filter(|b| PRIVATE_KEY_LABELS.contains(&b.block_type)) {
+        let public_key = match private_key_public_key(key) {
+            Ok(Some(public_key)) => public_key,
+            Ok(None) => {
+                undetermined_keys.push(key);
+                continue;
+            }
+            Err(e) => {
+                invalid_blocks.push((key, e));
+                continue;
+            }
+        };
+        match certificates.iter().find(|c| c.1 == public_key) {
+            Some(&(cert, _)) => pairs.push((key, cert)),
+            None => unmatched_keys.push(key),
+        }
+    }
+    let unmatched_certificates = certificates
+        .iter()
+        .map(|c| c.0)
+        .filter(|&cert| !pairs.iter().any(|p| ptr::eq(p.1, cert)))
+        .collect();
+    KeyMatches { pairs, unmatched_keys, undetermined_keys, unmatched_certificates, invalid_blocks }
+}
+
+/// algorithm of a private or public key
+#[derive(Debug, PartialEq, Clone)]
+pub enum KeyAlgorithm {
+    Rsa,
+    RsaPss,
+    /// elliptic curve key on the named curve, e.g. `P-256`
+    Ec(String),
+    Ed25519,
+    Ed448,
+    X25519,
+    X448,
+    Dsa,
+    Dh,
+    /// any other algorithm, by OID
+    Other(String),
+}
+
+/// algorithm and size of a key
+#[derive(Debug, PartialEq, Clone)]
+pub struct KeyInfo {
+    pub algorithm: KeyAlgorithm,
+    /// key size in bits as reported by OpenSSL, 0 for unknown algorithms
+    pub bits: usize,
+}
+
+static NAMED_CURVES: &[(&[u8], &str, usize)] = &[
+    (&[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x01], "P-192", 192),
+    (&[0x2b, 0x81, 0x04, 0x00, 0x21], "P-224", 224),
+    (OID_SECP256R1, "P-256", 256),
+    (OID_SECP384R1, "P-384", 384),
+    (OID_SECP521R1, "P-521", 521),
+    (&[0x2b, 0x81, 0x04, 0x00, 0x0a], "secp256k1", 256),
+    (&[0x2b, 0x24, 0x03, 0x03, 0x02, 0x08, 0x01, 0x01, 0x07], "brainpoolP256r1", 256),
+    (&[0x2b, 0x24, 0x03, 0x03, 0x02, 0x08, 0x01, 0x01, 0x0b], "brainpoolP384r1", 384),
+    (&[0x2b, 0x24, 0x03, 0x03, 0x02, 0x08, 0x01, 0x01, 0x0d], "brainpoolP512r1", 512),
+];
+
+/// name and size of a curve given as ECParameters, `field_bytes` is used for unknown curves
+fn ec_key_info(parameters: Option<&[u8]>, field_bytes: usize) -> Result<KeyInfo, PemParsingError> {
+    let curve = match parameters {
+        Some(parameters) => DerReader::new(parameters).read(TAG_OID)?,
+        None => return Err(PemParsingError::DerError(String::from("missing EC parameters"))),
+    };
+    let (name, bits) = match NAMED_CURVES.iter().find(|c| c.0 == curve) {
+        Some(&(_, name, bits)) => (String::from(name), bits),
+        None => (oid_to_string(curve), field_bytes * 8),
+    };
+    Ok(KeyInfo { algorithm: KeyAlgorithm::Ec(name), bits })
+}
+
+/// size of the prime of DSA and DH domain parameters
+fn prime_bits(parameters: Option<&[u8]>) -> Result<usize, PemParsingError> {
+    match parameters {
+        Some(parameters) => {
+            let mut parameters = DerReader::new(parameters).read_sequence()?;
+            Ok(bit_length(parameters.read(TAG_INTEGER)?))
+        }
+        None => Err(PemParsingError::DerError(String::from("missing domain parameters"))),
+    }
+}
+
+/// key info for algorithms whose size only depends on the AlgorithmIdentifier
+fn fixed_key_info(oid: &[u8], parameters: Option<&[u8]>) -> Result<KeyInfo, PemParsingError> {
+    let (algorithm, bits) = if oid == OID_ED25519 {
+        (KeyAlgorithm::Ed25519, 253)
+    } else if oid == OID_ED448 {
+        (KeyAlgorithm::Ed448, 456)
+    } else if oid == OID_X25519 {
+        (KeyAlgorithm::X25519, 253)
+    } else if oid == OID_X448 {
+        (KeyAlgorithm::X448, 448)
+    } else if oid == OID_DSA {
+        (KeyAlgorithm::Dsa, prime_bits(parameters)?)
+    } else if oid == OID_DH_KEY_AGREEMENT || oid == OID_DH_PUBLIC_NUMBER {
+        (KeyAlgorithm::Dh, prime_bits(parameters)?)
+    } else {
+        (KeyAlgorithm::Other(oid_to_string(oid)), 0)
+    };
+    Ok(KeyInfo { algorithm, bits })
+}
+
+fn rsa_algorithm(oid: &[u8]) -> KeyAlgorithm {
+    if oid == OID_RSASSA_PSS { KeyAlgorithm::RsaPss } else { KeyAlgorithm::Rsa }
+}
+
+fn rsa_key_info(algorithm: KeyAlgorithm, key: PublicKeyId) -> KeyInfo {
+    match key {
+        PublicKeyId::Rsa { modulus, .. } => KeyInfo { algorithm, bits: bit_length(modulus) },
+        _ => KeyInfo { algorithm, bits: 0 },
+    }
+}
+
+fn ec_private_key_size(der: &[u8]) -> Result<usize, PemParsingError> {
+    let mut key = DerReader::new(der).read_sequence()?;
+    key.read(TAG_INTEGER)?;
+    Ok(key.read(TAG_OCTET_STRING)?.len())
+}
+
+fn ec_private_key_parameters(der: &[u8]) -> Result<Option<&[u8]>, PemParsingError> {
+    let mut key = DerReader::new(der).read_sequence()?;
+    key.read(TAG_INTEGER)?;
+    key.read(TAG_OCTET_STRING)?;
+    match key.read_optional(tag_explicit(0))? {
+        Some(parameters) => DerReader::new(parameters).read_raw().map(Some),
+        None => Ok(None),
+    }
+}
+
+fn pkcs8_key_info(der: &[u8]) -> Result<KeyInfo, PemParsingError> {
+    let (oid, parameters, private_key) = parse_pkcs8(der)?;
+    if oid == OID_RSA_ENCRYPTION || oid == OID_RSASSA_PSS {
+        Ok(rsa_key_info(rsa_algorithm(oid), rsa_private_key(private_key)?))
+    } else if oid == OID_EC_PUBLIC_KEY {
+        let parameters = match parameters {
+            Some(parameters) => Some(parameters),
+            None => ec_private_key_parameters(private_key)?,
+        };
+        ec_key_info(parameters, ec_private_key_size(private_key)?)
+    } else {
+        fixed_key_info(oid, parameters)
+    }
+}
+
+fn spki_key_info(der: &[u8]) -> Result<KeyInfo, PemParsingError> {
+    let mut spki = DerReader::new(der).read_sequence()?;
+    let mut algorithm = spki.read_sequence()?;
+    let oid = algorithm.read(TAG_OID)?;
+    let parameters = if algorithm.is_empty() { None } else { Some(algorithm.read_raw()?) };
+    let key = spki.read_bit_string()?;
+    if oid == OID_RSA_ENCRYPTION || oid == OID_RSASSA_PSS {
+        Ok(rsa_key_info(rsa_algorithm(oid), rsa_public_key(key)?))
+    } else if oid == OID_EC_PUBLIC_KEY {
+        ec_key_info(parameters, key.len() / 2)
+    } else {
+        fixed_key_info(oid, parameters)
+    }
+}
+
+impl<'a> Block<'a> {
+    /// Reports algorithm and size of a `PRIVATE KEY` or `PUBLIC KEY` block.
+    ///
+    /// The legacy `RSA PRIVATE KEY`, `RSA PUBLIC KEY` and `EC PRIVATE KEY` blocks are supported as well.
+    pub fn key_info(&self) -> Result<KeyInfo, PemParsingError> {
+        match self.block_type {
+            "PRIVATE KEY" => pkcs8_key_info(&self.data),
+            "PUBLIC KEY" => spki_key_info(&self.data),
+            "RSA PRIVATE KEY" => Ok(rsa_key_info(KeyAlgorithm::Rsa, rsa_private_key(&self.data)?)),
+            "RSA PUBLIC KEY" => Ok(rsa_key_info(KeyAlgorithm::Rsa, rsa_public_key(&self.data)?)),
+            "EC PRIVATE KEY" => ec_key_info(ec_private_key_parameters(&self.data)?, ec_private_key_size(&self.data)?),
+            _ => Err(PemParsingError::UnexpectedBlockType(String::from(self.block_type))),
+        }
+    }
+}
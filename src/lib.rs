@@ -31,59 +31,205 @@
 //!```
 //!
 //!
+//! Without the default `std` feature the crate is `no_std` and only needs `alloc`.
+//!
+//! With the `serde` feature blocks are (de)serialized as their PEM encoding, see the
+//! `serde` module.
+//!
+//! With the `zeroize` feature the data of blocks and OpenSSH key pairs is wiped when they
+//! are dropped, and the `Debug` output of private key blocks does not show their data.
+//!
+#![cfg_attr(not(feature = "std"), no_std)]
 #[macro_use]
 extern crate nom;
-#[cfg(not(std))]
-extern crate core;
+#[cfg(not(feature = "std"))]
+#[macro_use]
+extern crate alloc;
+#[cfg(feature = "zeroize")]
+extern crate zeroize;
+#[cfg(feature = "serde")]
+extern crate serde as serde_crate;
 
 mod parsers;
 
 mod base64;
 
-pub use parsers::{pem_block, pem_blocks};
+pub use parsers::{pem_block, pem_block_constant_time, pem_blocks};
 
 use nom::Err as NomErr;
+use nom::IResult;
+#[cfg(not(feature = "std"))]
+use alloc::{string::String, vec::Vec};
+#[cfg(feature = "std")]
+use std::error;
+#[cfg(feature = "zeroize")]
+use zeroize::Zeroize;
 
 mod headers;
 
 pub use headers::{HeaderEntry, RFC1423Algorithm, ProcTypeType};
+pub use headers::{AsymmetricID, MICInfo, RFC1421KeyInfo, SymmetricID};
 
 mod display;
 
-use display::{write_base64, write_headers};
+use display::{write_base64, write_cleartext, write_headers};
+
+mod der;
+
+mod x509;
+
+pub use x509::{AltName, Asn1Time, CertificateSummary};
+
+mod chain;
+
+pub use chain::{order_chain, CertificateChain};
+
+mod keys;
+
+pub use keys::{match_keys, KeyAlgorithm, KeyInfo, KeyMatches};
+
+mod convert;
+
+pub use convert::{pkcs1_to_pkcs8, pkcs8_to_pkcs1, sec1_to_pkcs8, pkcs8_to_sec1};
+
+mod ssh2;
+
+pub use ssh2::{decode_ssh2_public_key, ssh2_public_key, Ssh2PublicKey};
+
+mod openssh;
+
+pub use openssh::{decode_openssh_public_key, openssh_public_key, ssh_blob_to_spki, spki_to_ssh_blob, OpenSshPublicKey};
+pub use openssh::{KdfOptions, OpenSshKeyPair, OpenSshPrivateKey, OpenSshPrivateSection};
+
+mod validate;
+
+pub use validate::HeaderViolation;
+
+mod crl;
+
+pub use crl::{crl_message, CrlEntry};
+
+mod canonical;
+
+pub use canonical::{canonicalize, decanonicalize};
+
+mod borrowed;
+
+pub use borrowed::{decode_block_into, BlockRef, BlockRefError, HeaderRef, HeaderRefs};
+
+mod armor;
+
+pub use armor::{crc24, decode_armor, pgp_armor, ArmorType, PgpArmor};
+pub use armor::{decode_signed_message, pgp_signed_message, CleartextSignedMessage};
+
+mod owned;
+
+pub use owned::OwnedBlock;
+
+mod normalize;
+
+pub use normalize::{decode_block_normalized, decode_blocks_normalized, normalize, Repair};
+
+mod scan;
+
+pub use scan::{scan_blocks, ScannedBlock};
+
+mod spans;
+
+pub use spans::{decode_blocks_with_spans, BlockSpans, Span};
+
+mod traits;
+
+pub use traits::{FromPem, ToPem};
+
+#[cfg(feature = "serde")]
+pub mod serde;
+
+/// names used by `serde_der!` and the derive macros of `nom_pem_macros`, which cannot rely on
+/// the prelude of `no_std` crates or on the name under which the calling crate imports serde
+#[doc(hidden)]
+pub mod __private {
+    #[cfg(not(feature = "std"))]
+    pub use alloc::vec::Vec;
+    #[cfg(feature = "std")]
+    pub use std::vec::Vec;
+    #[cfg(not(feature = "std"))]
+    pub use core::result::Result;
+    #[cfg(feature = "std")]
+    pub use std::result::Result;
+    #[cfg(not(feature = "std"))]
+    pub use core::convert::{AsRef, From};
+    #[cfg(feature = "std")]
+    pub use std::convert::{AsRef, From};
+    #[cfg(feature = "serde")]
+    pub use serde_crate::{Deserializer, Serializer};
+}
 
 /// structure representing one PEM block
-#[derive(Debug, PartialEq)]
+#[cfg_attr(not(feature = "zeroize"), derive(Debug))]
+#[derive(PartialEq)]
 pub struct Block<'a> {
     pub block_type: &'a str,
     pub headers: Vec<HeaderEntry<'a>>,
     pub data: Vec<u8>,
+    /// how `data` is represented in the message
+    pub encoding: BodyEncoding,
+    /// positions of the parts of the block in the input of `decode_blocks_with_spans`,
+    /// `None` for blocks read by the other parsers or built in code
+    pub spans: Option<BlockSpans>,
+}
+
+/// representation of the body of a block
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum BodyEncoding {
+    /// base64 encoded binary data
+    Base64,
+    /// unencoded text of a `Proc-Type: 4,MIC-CLEAR` message, see RFC 1421 section 4.6.1.1.3,
+    /// lines starting with a dash are escaped with `- `
+    Clear,
 }
 
 
 #[derive(Debug)]
 pub enum PemParsingError {
-    NomError(String)
+    NomError(String),
+    /// the block data is not the expected DER structure
+    DerError(String),
+    /// the operation is not supported for blocks of this type
+    UnexpectedBlockType(String),
+    /// the data is not the expected SSH key structure
+    SshError(String),
+    /// the OpenPGP armor is malformed or its checksum does not match
+    ArmorError(String),
+    /// the text is not in the expected character set or content domain
+    TextError(String),
+    /// the operation needs the plain data, but the block of this type is encrypted
+    EncryptedBlock(String),
+    /// the operation is not supported for messages with this Proc-Type, empty if there is none
+    UnexpectedProcType(String),
 }
 
-pub fn decode_block<'a>(input: &[u8]) -> Result<Block, PemParsingError> {
-    match pem_block(input) {
-        Err(NomErr::Error(e)) => {
-            let error_kind = e.into_error_kind();
-            Err(PemParsingError::NomError(String::from(error_kind.description())))
-        }
-        Err(NomErr::Failure(e)) => {
-            let error_kind = e.into_error_kind();
-            Err(PemParsingError::NomError(String::from(error_kind.description())))
+impl fmt::Display for PemParsingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PemParsingError::NomError(ref e) => write!(f, "parsing error: {}", e),
+            PemParsingError::DerError(ref e) => write!(f, "DER error: {}", e),
+            PemParsingError::UnexpectedBlockType(ref e) => write!(f, "unexpected block type: {}", e),
+            PemParsingError::SshError(ref e) => write!(f, "SSH key error: {}", e),
+            PemParsingError::ArmorError(ref e) => write!(f, "armor error: {}", e),
+            PemParsingError::TextError(ref e) => write!(f, "text error: {}", e),
+            PemParsingError::EncryptedBlock(ref e) => write!(f, "encrypted block: {}", e),
+            PemParsingError::UnexpectedProcType(ref e) => write!(f, "unexpected Proc-Type: {}", e),
         }
-        Err(NomErr::Incomplete(_i)) => Err(PemParsingError::NomError(format!("incomplete: {:?}", _i))),
-        Ok((_rest, block)) => Ok(block),
     }
 }
 
+#[cfg(feature = "std")]
+impl error::Error for PemParsingError {}
 
-pub fn decode_blocks<'a>(input: &[u8]) -> Result<Vec<Block>, PemParsingError> {
-    match pem_blocks(input) {
+/// maps the result of a nom parser to the parsed value or a `PemParsingError`
+fn nom_result<T>(result: IResult<&[u8], T>) -> Result<T, PemParsingError> {
+    match result {
         Err(NomErr::Error(e)) => {
             let error_kind = e.into_error_kind();
             Err(PemParsingError::NomError(String::from(error_kind.description())))
@@ -93,20 +239,91 @@ pub fn decode_blocks<'a>(input: &[u8]) -> Result<Vec<Block>, PemParsingError> {
             Err(PemParsingError::NomError(String::from(error_kind.description())))
         }
         Err(NomErr::Incomplete(_i)) => Err(PemParsingError::NomError(format!("incomplete: {:?}", _i))),
-        Ok((_rest, blocks)) => Ok(blocks),
+        Ok((_rest, value)) => Ok(value),
     }
 }
 
-#[cfg(not(std))]
+pub fn decode_block<'a>(input: &'a [u8]) -> Result<Block<'a>, PemParsingError> {
+    nom_result(pem_block(input))
+}
+
+/// Decodes a block like `decode_block`, but the base64 data is decoded in constant time
+/// for any label, not just for those containing `PRIVATE KEY`.
+pub fn decode_block_constant_time<'a>(input: &'a [u8]) -> Result<Block<'a>, PemParsingError> {
+    nom_result(pem_block_constant_time(input))
+}
+
+
+pub fn decode_blocks<'a>(input: &'a [u8]) -> Result<Vec<Block<'a>>, PemParsingError> {
+    nom_result(pem_blocks(input))
+}
+
+#[cfg(not(feature = "std"))]
 use core::fmt;
-#[cfg(std)]
+#[cfg(feature = "std")]
 use std::fmt;
 
+// fields cannot be moved out of a block while this is implemented
+#[cfg(feature = "zeroize")]
+impl<'a> Drop for Block<'a> {
+    fn drop(&mut self) {
+        self.data.zeroize();
+    }
+}
+
+/// like the derived `Debug`, but the data of private key blocks is redacted
+#[cfg(feature = "zeroize")]
+impl<'a> fmt::Debug for Block<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut s = f.debug_struct("Block");
+        s.field("block_type", &self.block_type);
+        s.field("headers", &self.headers);
+        if self.block_type.contains("PRIVATE KEY") {
+            s.field("data", &format_args!("[REDACTED; {} bytes]", self.data.len()));
+        } else {
+            s.field("data", &self.data);
+        }
+        s.field("encoding", &self.encoding);
+        s.field("spans", &self.spans).finish()
+    }
+}
+
+/// the BEGIN line and the headers of a block
+struct Begin<'b, 'a: 'b>(&'b Block<'a>);
+
+impl<'b, 'a> fmt::Display for Begin<'b, 'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "-----BEGIN {}-----", &self.0.block_type)?;
+        write_headers(f, &self.0.headers)
+    }
+}
+
 impl<'a> fmt::Display for Block<'a> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "-----BEGIN {}-----\n", &self.block_type)?;
-        write_headers(f, &self.headers)?;
-        write_base64(f, &self.data, 64)?;
-        write!(f, "-----END {}-----\n", &self.block_type)
+        write!(f, "{}", Begin(self))?;
+        match self.encoding {
+            BodyEncoding::Base64 => write_base64(f, &self.data, 64)?,
+            // clear text that is not UTF-8 is only kept by `encode_block`
+            BodyEncoding::Clear => {
+                let mut text = Vec::with_capacity(self.data.len() + 2);
+                write_cleartext(&mut text, &self.data);
+                f.write_str(&String::from_utf8_lossy(&text))?;
+            }
+        }
+        writeln!(f, "-----END {}-----", &self.block_type)
+    }
+}
+
+/// Encodes a block like its `Display` implementation, but writes the clear text of MIC-CLEAR
+/// messages byte for byte, where `Display` has to replace what is not UTF-8.
+pub fn encode_block(block: &Block) -> Vec<u8> {
+    match block.encoding {
+        BodyEncoding::Base64 => format!("{}", block).into_bytes(),
+        BodyEncoding::Clear => {
+            let mut ret = format!("{}", Begin(block)).into_bytes();
+            write_cleartext(&mut ret, &block.data);
+            ret.extend_from_slice(format!("-----END {}-----\n", block.block_type).as_bytes());
+            ret
+        }
     }
 }
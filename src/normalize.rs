@@ -0,0 +1,283 @@
+//! Recovery of PEM that was mangled on its way through environment variables, JSON,
+//! YAML or single line configuration values.
+
+#[cfg(not(feature = "std"))]
+use core::{fmt, mem};
+#[cfg(feature = "std")]
+use std::{fmt, mem};
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+#[cfg(feature = "zeroize")]
+use zeroize::Zeroize;
+use super::{decode_block, decode_blocks, OwnedBlock, PemParsingError};
+use super::parsers::{find, DASHES, PEM_END, PEM_START};
+
+/// a repair applied by `normalize`
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Repair {
+    /// the input was enclosed in quotes
+    Quoted,
+    /// the line endings were written as `\n` or `\r\n` escape sequences
+    EscapedNewlines,
+    /// `\r\n` or `\r` line endings were replaced by `\n`
+    LineEndings,
+    /// the lines were indented, e.g. in a YAML block scalar
+    Indented,
+    /// a block was written on a single line, with spaces or nothing in place of the line endings
+    Flattened,
+}
+
+impl fmt::Display for Repair {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Repair::Quoted => write!(f, "removed enclosing quotes"),
+            Repair::EscapedNewlines => write!(f, "unescaped line endings"),
+            Repair::LineEndings => write!(f, "converted line endings"),
+            Repair::Indented => write!(f, "removed indentation"),
+            Repair::Flattened => write!(f, "split a single line block into lines"),
+        }
+    }
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    find(haystack, needle).is_some()
+}
+
+fn replace(input: &[u8], from: &[u8], to: &[u8]) -> Vec<u8> {
+    let mut ret = Vec::with_capacity(input.len());
+    let mut rest = input;
+    while let Some(i) = find(rest, from) {
+        ret.extend_from_slice(&rest[..i]);
+        ret.extend_from_slice(to);
+        rest = &rest[i + from.len()..];
+    }
+    ret.extend_from_slice(rest);
+    ret
+}
+
+fn is_blank(c: u8) -> bool {
+    c == b' ' || c == b'\t'
+}
+
+fn trim(mut input: &[u8]) -> &[u8] {
+    while let Some((&c, rest)) = input.split_first() {
+        if !c.is_ascii_whitespace() { break; }
+        input = rest;
+    }
+    while let Some((&c, rest)) = input.split_last() {
+        if !c.is_ascii_whitespace() { break; }
+        input = rest;
+    }
+    input
+}
+
+fn unquote(input: &[u8]) -> Option<&[u8]> {
+    match (input.first(), input.last()) {
+        (Some(&a), Some(&b)) if input.len() > 1 && a == b && (a == b'"' || a == b'\'') => Some(&input[1..input.len() - 1]),
+        _ => None,
+    }
+}
+
+/// removes the indentation common to all lines but the first, whose indentation was
+/// usually lost with the leading whitespace of the value, deeper indented header
+/// continuation lines keep their extra indentation
+fn dedent(input: &[u8]) -> Option<Vec<u8>> {
+    let indent = |line: &[u8]| line.iter().take_while(|&&c| is_blank(c)).count();
+    let lines: Vec<&[u8]> = input.split(|&c| c == b'\n').collect();
+    let common = lines[1..].iter().filter(|l| !trim(l).is_empty()).map(|l| indent(l)).min().unwrap_or(0);
+    if common == 0 && indent(lines[0]) == 0 {
+        return None;
+    }
+    let mut ret = Vec::with_capacity(input.len());
+    for (i, line) in lines.iter().enumerate() {
+        if i > 0 {
+            ret.push(b'\n');
+        }
+        let skip = if i == 0 { indent(line) } else { common.min(line.len()) };
+        ret.extend_from_slice(&line[skip..]);
+    }
+    Some(ret)
+}
+
+/// a `Name:` token starting a header field
+// `Option::is_some_and` needs Rust 1.70
+#[allow(clippy::unnecessary_map_or)]
+fn is_header_name(word: &[u8]) -> bool {
+    match word.split_last() {
+        Some((&b':', name)) => {
+            name.first().map_or(false, |c| c.is_ascii_alphabetic())
+                && name.iter().all(|&c| c.is_ascii_alphanumeric() || c == b'-')
+        }
+        _ => false,
+    }
+}
+
+fn is_base64(word: &[u8]) -> bool {
+    word.iter().all(|&c| c.is_ascii_alphanumeric() || c == b'+' || c == b'/' || c == b'=')
+}
+
+/// index of the first word of the base64 body at the end of `words`, i.e. lines of the same
+/// length, a multiple of 4, followed by a shorter or equally long last line
+// `usize::is_multiple_of` needs Rust 1.87
+#[allow(clippy::manual_is_multiple_of)]
+fn body_start(words: &[&[u8]]) -> usize {
+    let last = match words.last() {
+        Some(last) if is_base64(last) => last.len(),
+        _ => return words.len(),
+    };
+    let mut start = words.len() - 1;
+    while start > 0 {
+        let line = words[start - 1];
+        let width = if start + 1 < words.len() { words[start].len() } else { line.len() };
+        if !is_base64(line) || line.len() % 4 != 0 || line.len() != width || line.len() < last {
+            break;
+        }
+        start -= 1;
+    }
+    start
+}
+
+/// splits the blocks whose BEGIN and END line are on the same line, following RFC 1421 the value
+/// of a header field runs up to the next `Name:` token or to the base64 body
+fn unflatten(input: &[u8]) -> Option<Vec<u8>> {
+    let mut ret = Vec::with_capacity(input.len() + input.len() / 32);
+    let mut rest = input;
+    let mut repaired = false;
+    while let Some(start) = find(rest, PEM_START) {
+        let label_end = start + PEM_START.len() + find(&rest[start + PEM_START.len()..], DASHES)? + DASHES.len();
+        let end = label_end + find(&rest[label_end..], PEM_END)?;
+        let footer_end = end + PEM_END.len() + find(&rest[end + PEM_END.len()..], DASHES)? + DASHES.len();
+        ret.extend_from_slice(&rest[..label_end]);
+        let inner = &rest[label_end..end];
+        if contains(inner, b"\n") {
+            ret.extend_from_slice(&rest[label_end..footer_end]);
+        } else {
+            repaired = true;
+            ret.push(b'\n');
+            let words: Vec<&[u8]> = inner.split(|&c| is_blank(c)).filter(|w| !w.is_empty()).collect();
+            let body = body_start(&words);
+            let mut i = 0;
+            while i < body && is_header_name(words[i]) {
+                ret.extend_from_slice(words[i]);
+                i += 1;
+                while i < body && !is_header_name(words[i]) {
+                    ret.push(b' ');
+                    ret.extend_from_slice(words[i]);
+                    i += 1;
+                }
+                ret.push(b'\n');
+            }
+            if i > 0 {
+                ret.push(b'\n');
+            }
+            let mut body: Vec<u8> = words[i..].concat();
+            for line in body.chunks(64) {
+                ret.extend_from_slice(line);
+                ret.push(b'\n');
+            }
+            wipe(&mut body);
+            ret.extend_from_slice(&rest[end..footer_end]);
+        }
+        rest = &rest[footer_end..];
+    }
+    ret.extend_from_slice(rest);
+    if repaired { Some(ret) } else { None }
+}
+
+/// wipes an intermediate copy of the input, which may hold key material
+#[cfg(feature = "zeroize")]
+pub fn wipe(buf: &mut Vec<u8>) {
+    buf.zeroize();
+}
+
+#[cfg(not(feature = "zeroize"))]
+pub fn wipe(_: &mut Vec<u8>) {}
+
+/// replaces `pem` by its repaired form and wipes the old buffer
+fn update(pem: &mut Vec<u8>, repaired: Vec<u8>) {
+    wipe(&mut mem::replace(pem, repaired));
+}
+
+/// Recovers PEM that was quoted, had its line endings escaped as `\n`, converted to `\r\n`
+/// or flattened into spaces, or was indented, and lists the repairs in the order they were applied.
+///
+/// The result ends with a line ending, as needed by `decode_blocks`.
+pub fn normalize(input: &[u8]) -> (Vec<u8>, Vec<Repair>) {
+    let mut repairs = Vec::new();
+    let mut pem = trim(input);
+    if let Some(unquoted) = unquote(pem) {
+        if contains(unquoted, PEM_START) {
+            repairs.push(Repair::Quoted);
+            pem = trim(unquoted);
+        }
+    }
+    let mut pem = pem.to_vec();
+    if contains(&pem, b"\\n") || contains(&pem, b"\\r") {
+        repairs.push(Repair::EscapedNewlines);
+        for &(from, to) in [(&b"\\r\\n"[..], &b"\n"[..]), (b"\\n", b"\n"), (b"\\r", b"\n"), (b"\\/", b"/")].iter() {
+            // JSON may escape the slashes of base64 too
+            let replaced = replace(&pem, from, to);
+            update(&mut pem, replaced);
+        }
+        let trimmed = trim(&pem).to_vec();
+        update(&mut pem, trimmed);
+    }
+    if contains(&pem, b"\r") {
+        repairs.push(Repair::LineEndings);
+        for &(from, to) in [(&b"\r\n"[..], &b"\n"[..]), (b"\r", b"\n")].iter() {
+            let replaced = replace(&pem, from, to);
+            update(&mut pem, replaced);
+        }
+    }
+    if let Some(dedented) = dedent(&pem) {
+        repairs.push(Repair::Indented);
+        update(&mut pem, dedented);
+    }
+    if let Some(unflattened) = unflatten(&pem) {
+        repairs.push(Repair::Flattened);
+        update(&mut pem, unflattened);
+    }
+    if pem.last() != Some(&b'\n') {
+        pem.push(b'\n');
+    }
+    (pem, repairs)
+}
+
+/// Decodes the first block of `input` after `normalize` and returns it with the applied repairs.
+pub fn decode_block_normalized(input: &[u8]) -> Result<(OwnedBlock, Vec<Repair>), PemParsingError> {
+    let (mut pem, repairs) = normalize(input);
+    let block = decode_block(&pem).map(|block| OwnedBlock::from(&block));
+    wipe(&mut pem);
+    Ok((block?, repairs))
+}
+
+/// Decodes all blocks of `input` after `normalize` and returns them with the applied repairs.
+pub fn decode_blocks_normalized(input: &[u8]) -> Result<(Vec<OwnedBlock>, Vec<Repair>), PemParsingError> {
+    let (mut pem, repairs) = normalize(input);
+    let blocks = decode_blocks(&pem).map(|blocks| blocks.iter().map(OwnedBlock::from).collect());
+    wipe(&mut pem);
+    Ok((blocks?, repairs))
+}
+
+#[cfg(test)]
+#[test]
+fn test_normalize() {
+    let pem = &b"-----BEGIN X-----\nTWFu\n-----END X-----\n"[..];
+    assert_eq!((pem.to_vec(), vec![]), normalize(pem));
+    assert_eq!((pem.to_vec(), vec![]), normalize(&pem[..pem.len() - 1]));
+    assert_eq!((pem.to_vec(), vec![Repair::Quoted, Repair::EscapedNewlines]),
+               normalize(b"\"-----BEGIN X-----\\nTWFu\\n-----END X-----\\n\""));
+    assert_eq!((pem.to_vec(), vec![Repair::LineEndings]), normalize(b"-----BEGIN X-----\r\nTWFu\r\n-----END X-----\r\n"));
+    assert_eq!((pem.to_vec(), vec![Repair::Indented]), normalize(b"  -----BEGIN X-----\n  TWFu\n  -----END X-----\n"));
+    assert_eq!((pem.to_vec(), vec![Repair::Flattened]), normalize(b"-----BEGIN X----- TWFu -----END X-----"));
+    assert_eq!((pem.to_vec(), vec![Repair::Flattened]), normalize(b"-----BEGIN X-----TWFu-----END X-----"));
+    assert_eq!((b"-----BEGIN X-----\nA: 1\nB: 2\n\nTWFu\n-----END X-----\n".to_vec(), vec![Repair::Flattened]),
+               normalize(b"-----BEGIN X----- A: 1 B: 2 TWFu -----END X-----"));
+    assert_eq!((b"-----BEGIN X-----\nComment: two words\n\nTWFu\n-----END X-----\n".to_vec(), vec![Repair::Flattened]),
+               normalize(b"-----BEGIN X----- Comment: two words TWFu -----END X-----"));
+    assert_eq!((b"-----BEGIN X-----\nA: 1\n\nTWFuTWFuTW==\n-----END X-----\n".to_vec(), vec![Repair::Flattened]),
+               normalize(b"-----BEGIN X----- A: 1 TWFu TWFu TW== -----END X-----"));
+    assert_eq!((b"-----BEGIN X-----\nA: 1,\n 2\n\nTWFu\n-----END X-----\n".to_vec(), vec![Repair::Indented]),
+               normalize(b"-----BEGIN X-----\n    A: 1,\n     2\n\n    TWFu\n    -----END X-----"));
+}
@@ -0,0 +1,99 @@
+//! A block that owns all of its parts, for keeping blocks beyond the lifetime of the input.
+
+#[cfg(not(feature = "std"))]
+use core::fmt;
+#[cfg(feature = "std")]
+use std::fmt;
+
+#[cfg(not(feature = "std"))]
+use alloc::{string::String, vec::Vec};
+use super::{Block, BodyEncoding};
+use super::headers::typed_entry;
+#[cfg(feature = "zeroize")]
+use zeroize::Zeroize;
+
+/// a PEM block that does not borrow the input, the headers are kept as their name and fields
+#[cfg_attr(not(feature = "zeroize"), derive(Debug))]
+#[derive(PartialEq, Clone)]
+pub struct OwnedBlock {
+    pub block_type: String,
+    pub headers: Vec<(String, Vec<String>)>,
+    pub data: Vec<u8>,
+    pub encoding: BodyEncoding,
+}
+
+impl OwnedBlock {
+    /// a `Block` borrowing the label and header names, with typed headers and a copy of the data
+    pub fn block(&self) -> Block<'_> {
+        Block {
+            block_type: &self.block_type,
+            headers: self.headers.iter().map(|(key, values)| typed_entry(key, values.clone())).collect(),
+            data: self.data.clone(),
+            encoding: self.encoding,
+            spans: None,
+        }
+    }
+}
+
+impl<'a, 'b> From<&'b Block<'a>> for OwnedBlock {
+    fn from(block: &'b Block<'a>) -> OwnedBlock {
+        OwnedBlock {
+            block_type: String::from(block.block_type),
+            headers: block.headers.iter().map(|h| {
+                let (key, values) = h.fields();
+                (String::from(key), values)
+            }).collect(),
+            data: block.data.clone(),
+            encoding: block.encoding,
+        }
+    }
+}
+
+impl fmt::Display for OwnedBlock {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.block(), f)
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for OwnedBlock {
+    fn drop(&mut self) {
+        self.data.zeroize();
+    }
+}
+
+/// like the derived `Debug`, but the data of private key blocks is redacted
+#[cfg(feature = "zeroize")]
+impl fmt::Debug for OwnedBlock {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut s = f.debug_struct("OwnedBlock");
+        s.field("block_type", &self.block_type);
+        s.field("headers", &self.headers);
+        if self.block_type.contains("PRIVATE KEY") {
+            s.field("data", &format_args!("[REDACTED; {} bytes]", self.data.len()));
+        } else {
+            s.field("data", &self.data);
+        }
+        s.field("encoding", &self.encoding).finish()
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_owned_block() {
+    let input = b"-----BEGIN PRIVACY-ENHANCED MESSAGE-----
+Proc-Type: 4,ENCRYPTED
+Content-Domain: RFC822
+DEK-Info: DES-CBC,F8143EDE5960C597
+X-Custom: a,b
+
+TWFu
+-----END PRIVACY-ENHANCED MESSAGE-----
+";
+    let block = super::decode_block(input).unwrap();
+    let owned = OwnedBlock::from(&block);
+    assert_eq!("PRIVACY-ENHANCED MESSAGE", owned.block_type);
+    assert_eq!((String::from("X-Custom"), vec![String::from("a"), String::from("b")]), owned.headers[3]);
+    assert_eq!(block, owned.block());
+    assert_eq!(format!("{}", block), format!("{}", owned));
+}
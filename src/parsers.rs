@@ -1,14 +1,24 @@
-static PEM_START: &'static [u8] = b"-----BEGIN ";
-static PEM_END: &'static [u8] = b"-----END ";
+pub static PEM_START: &[u8] = b"-----BEGIN ";
+pub static PEM_END: &[u8] = b"-----END ";
+pub static DASHES: &[u8] = b"-----";
 
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
 use nom::{IResult, Err, Needed, ErrorKind, is_space, line_ending};
-use super::{Block, base64, HeaderEntry};
-use super::headers::pem_headers;
-#[cfg(not(std))]
+use super::{Block, BodyEncoding, HeaderEntry, ProcTypeType};
+use super::base64::Decoder;
+use super::headers::pem_headers_rest_lens;
+use super::spans::{BlockSpans, Lines};
+#[cfg(not(feature = "std"))]
 use core::str::from_utf8;
-#[cfg(std)]
+#[cfg(feature = "std")]
 use std::str::from_utf8;
 
+/// position of the first occurrence of `needle` in `haystack`
+pub fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
 fn pem_dashed_string(i: &[u8]) -> IResult<&[u8], &str> {
     let mut found = 0;
     for pos in 0..i.len() {
@@ -60,7 +70,7 @@ X"));
 }
 
 #[inline(always)]
-fn cleanup_spaces(i: &[u8]) -> IResult<&[u8], ()> {
+pub fn cleanup_spaces(i: &[u8]) -> IResult<&[u8], ()> {
     for pos in 0..i.len() {
         let b = i[pos];
         if b == 10 || b == 13 || b == 32 {
@@ -79,9 +89,14 @@ fn test_cleanup_spaces() {
  XYX"));
 }
 
-named!(pub pem_footer<&str>, do_parse!(
+named!(pem_end_line<&str>, do_parse!(
     tag!(PEM_END) >>
     s: pem_dashed_string >>
+    (s)
+));
+
+named!(pub pem_footer<&str>, do_parse!(
+    s: pem_end_line >>
     cleanup_spaces >>
     (s)
 ));
@@ -93,18 +108,109 @@ fn test_pem_footer() {
 X"));
 }
 
-pub fn no_pem_headers(i: &[u8]) -> ::nom::IResult<&[u8], Vec<HeaderEntry>> {
-  Ok((i, Vec::new()))
+
+
+/// reads the cleartext body of a MIC-CLEAR message up to the END line,
+/// lines escaped with a leading "- " (RFC 934) are unescaped
+pub fn pem_cleartext(i: &[u8]) -> IResult<&[u8], Vec<u8>> {
+    let mut text = Vec::new();
+    let mut rest = i;
+    while !rest.starts_with(PEM_END) {
+        let end = match rest.iter().position(|&b| b == 10) {
+            Some(end) => end + 1,
+            None => return Err(Err::Incomplete(Needed::Size(1))),
+        };
+        let line = &rest[..end];
+        if line.starts_with(b"- ") {
+            text.extend_from_slice(&line[2..]);
+        } else if line.starts_with(b"-") {
+            return Err(Err::Error(error_position!(rest, ErrorKind::Custom(0xbb0005))));
+        } else {
+            text.extend_from_slice(line);
+        }
+        rest = &rest[end..];
+    }
+    // the line ending before the END line belongs to the boundary
+    if text.ends_with(b"\n") {
+        text.pop();
+        if text.ends_with(b"\r") {
+            text.pop();
+        }
+    }
+    Ok((rest, text))
 }
 
+#[cfg(test)]
+#[test]
+fn test_pem_cleartext() {
+    assert_eq!(Ok((&b"-----END X-----"[..], b"a\n-b\nc".to_vec())), pem_cleartext(b"a\n- -b\nc\n-----END X-----"));
+    assert!(pem_cleartext(b"a\n-b\n-----END X-----").is_err());
+}
 
-named!(pub pem_block<Block>,  do_parse!(
-    block_type: pem_begin >>
-    headers: alt!(pem_headers|no_pem_headers) >>
-    data : ws!(base64::base64) >>
-    pem_footer >>
-    (Block{block_type, headers, data})
-));
+fn is_mic_clear(headers: &[HeaderEntry]) -> bool {
+    headers.iter().any(|h| matches!(*h, HeaderEntry::ProcType(_, ProcTypeType::MIC_CLEAR)))
+}
+
+/// reads the body, which is base64 encoded unless the headers announce a MIC-CLEAR message
+fn pem_body(i: &[u8], clear: bool, decoder: Decoder) -> IResult<&[u8], (Vec<u8>, BodyEncoding)> {
+    if clear {
+        let (rest, text) = pem_cleartext(i)?;
+        Ok((rest, (text, BodyEncoding::Clear)))
+    } else {
+        let (rest, data) = ws!(i, call!(|i| decoder.decode(i)))?;
+        Ok((rest, (data, BodyEncoding::Base64)))
+    }
+}
+
+/// length of `line` without the trailing whitespace and line ending
+fn trimmed_len(line: &[u8]) -> usize {
+    line.iter().rposition(|c| !c.is_ascii_whitespace()).map_or(0, |i| i + 1)
+}
+
+/// the data of blocks with `PRIVATE KEY` in their label is always decoded in constant time,
+/// given the `lines` of an input that `i` is a suffix of, the positions of the parts of the block
+/// in that input are recorded in `Block::spans`
+fn pem_block_decoding<'a>(i: &'a [u8], constant_time: bool, lines: Option<&mut Lines>) -> IResult<&'a [u8], Block<'a>> {
+    let (headers_start, block_type) = pem_begin(i)?;
+    let (body_start, (headers, header_rest_lens)) = match pem_headers_rest_lens(headers_start) {
+        Err(Err::Error(_)) => (headers_start, (Vec::new(), Vec::new())),
+        headers => headers?,
+    };
+    let (end_start, (data, encoding)) = pem_body(body_start, is_mic_clear(&headers), Decoder::for_label(block_type, constant_time))?;
+    let (end_end, _) = pem_end_line(end_start)?;
+    let (rest, _) = cleanup_spaces(end_end)?;
 
-named!(pub pem_blocks<Vec<Block>>, many1!(pem_block));
+    let spans = lines.map(|lines| {
+        let start = lines.input_len() - i.len();
+        let pos = |rest: &[u8]| start + i.len() - rest.len();
+        let block = lines.span(start, pos(end_end));
+        let begin = lines.span(start, start + trimmed_len(&i[..i.len() - headers_start.len()]));
+        let mut header_start = headers_start;
+        let headers = header_rest_lens.iter().map(|&rest_len| {
+            let header = &header_start[..header_start.len() - rest_len];
+            let span = lines.span(pos(header_start), pos(header_start) + trimmed_len(header));
+            header_start = &header_start[header.len()..];
+            span
+        }).collect();
+        let body = lines.span(pos(body_start), pos(end_start));
+        BlockSpans { block, begin, headers, body, end: lines.span(pos(end_start), pos(end_end)) }
+    });
+    Ok((rest, Block { block_type, headers, data, encoding, spans }))
+}
+
+named!(pub pem_block<Block>, call!(pem_block_decoding, false, None));
+
+/// reads a block like `pem_block`, but decodes the data in constant time regardless of the label
+pub fn pem_block_constant_time(i: &[u8]) -> IResult<&[u8], Block<'_>> {
+    pem_block_decoding(i, true, None)
+}
+
+named!(pub pem_blocks<Vec<Block>>, fold_many1!(pem_block, Vec::new(), |mut blocks: Vec<_>, block| { blocks.push(block); blocks }));
+
+/// reads blocks like `pem_blocks` and records the positions of their parts in `i`,
+/// the lines are counted once for all blocks
+pub fn pem_blocks_with_spans(i: &[u8]) -> IResult<&[u8], Vec<Block<'_>>> {
+    let mut lines = Lines::new(i);
+    fold_many1!(i, call!(pem_block_decoding, false, Some(&mut lines)), Vec::new(), |mut blocks: Vec<_>, block| { blocks.push(block); blocks })
+}
 
@@ -0,0 +1,147 @@
+//! Search for PEM blocks embedded in arbitrary text, e.g. logs, mails, source code or HTML.
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+use super::{normalize, pem_block, OwnedBlock};
+use super::normalize::wipe;
+use super::parsers::{find, DASHES, PEM_END, PEM_START};
+
+/// a block found by `scan_blocks`
+#[derive(Debug, PartialEq, Clone)]
+pub struct ScannedBlock {
+    /// byte offset of the `-----BEGIN` marker in the input
+    pub offset: usize,
+    pub block: OwnedBlock,
+}
+
+/// end of the END marker of the block starting at `begin`
+fn region_end(input: &[u8], begin: usize) -> Option<usize> {
+    let end = begin + find(&input[begin..], PEM_END)? + PEM_END.len();
+    Some(end + find(&input[end..], DASHES)? + DASHES.len())
+}
+
+/// whether `line` ends with a closing quote, ignoring the operators and separators behind it
+fn ends_quoted(line: &[u8]) -> bool {
+    match line.iter().rposition(|&c| !c.is_ascii_whitespace() && !b",+;".contains(&c)) {
+        Some(i) => b"\"'`".contains(&line[i]),
+        None => false,
+    }
+}
+
+/// removes what quoting in source code or logs appends to a line, e.g. `\n",` or `\n" +`,
+/// `+` is a base64 character and is only removed behind a closing quote
+fn trim_line_suffix(mut line: &[u8]) -> &[u8] {
+    loop {
+        line = match *line {
+            [ref rest @ .., b'\\', b'n'] | [ref rest @ .., b'\\', b'r'] => rest,
+            [ref rest @ .., c] if c.is_ascii_whitespace() || b"\"'`\\)".contains(&c) => rest,
+            [ref rest @ .., c] if b",+;".contains(&c) && ends_quoted(rest) => rest,
+            _ => return line,
+        }
+    }
+}
+
+/// length of the indentation and opening quote of `line`, if it continues a string literal
+/// that was opened at the end of `prefix`, e.g. `String pem = "` followed by `    "`
+fn continued_literal(prefix: &[u8], line: &[u8]) -> Option<usize> {
+    let quote = *prefix.last().filter(|&q| b"\"'`".contains(q))?;
+    let indent = line.iter().position(|&c| c != b' ' && c != b'\t')?;
+    if line[indent] == quote { Some(indent + 1) } else { None }
+}
+
+/// the lines of the block starting at `begin`, without the prefix that the BEGIN line has
+/// in front of the marker, e.g. indentation, `> ` of mail quotes or `"` of string literals
+fn strip_line_prefix(input: &[u8], begin: usize) -> Option<(Vec<u8>, usize)> {
+    let line_start = input[..begin].iter().rposition(|&c| c == b'\n').map_or(0, |i| i + 1);
+    let prefix = &input[line_start..begin];
+    let mut text = Vec::new();
+    let mut pos = begin;
+    loop {
+        let line_end = input[pos..].iter().position(|&c| c == b'\n').map_or(input.len(), |i| pos + i);
+        let line = trim_line_suffix(&input[pos..line_end]);
+        text.extend_from_slice(line);
+        text.push(b'\n');
+        if find(line, PEM_END).is_some() {
+            return Some((text, line_end));
+        }
+        if line_end == input.len() {
+            return None;
+        }
+        pos = line_end + 1;
+        let line = &input[pos..];
+        if line.starts_with(prefix) {
+            pos += prefix.len();
+        } else if let Some(len) = continued_literal(prefix, line) {
+            pos += len;
+        } else {
+            // empty lines of quoted blocks may have lost the trailing blanks of the prefix
+            let trimmed = trim_line_suffix(prefix);
+            if !line.starts_with(trimmed) {
+                return None;
+            }
+            pos += trimmed.len();
+        }
+    }
+}
+
+/// parses the block at `begin`, returns it with the end of its input
+fn parse_at(input: &[u8], begin: usize) -> Option<(OwnedBlock, usize)> {
+    if let Ok((rest, block)) = pem_block(&input[begin..]) {
+        return Some((OwnedBlock::from(&block), input.len() - rest.len()));
+    }
+    if let Some((mut text, end)) = strip_line_prefix(input, begin) {
+        let found = pem_block(&text).ok().map(|(_, block)| (OwnedBlock::from(&block), end));
+        wipe(&mut text);
+        if found.is_some() {
+            return found;
+        }
+    }
+    let end = region_end(input, begin)?;
+    let (mut text, _) = normalize(&input[begin..end]);
+    let found = pem_block(&text).ok().map(|(_, block)| (OwnedBlock::from(&block), end));
+    wipe(&mut text);
+    found
+}
+
+/// Searches `input` for PEM blocks and returns all that parse, in the order of their offsets.
+///
+/// Each `-----BEGIN` marker is tried as is, then with the prefix of its line removed from
+/// all lines of the block together with trailing quotes and escaped line endings, and last
+/// after `normalize`. Markers that do not start a valid block are skipped.
+pub fn scan_blocks(input: &[u8]) -> Vec<ScannedBlock> {
+    let mut ret = Vec::new();
+    let mut pos = 0;
+    while let Some(i) = find(&input[pos..], PEM_START) {
+        let offset = pos + i;
+        match parse_at(input, offset) {
+            Some((block, end)) => {
+                ret.push(ScannedBlock { offset, block });
+                pos = end;
+            }
+            None => pos = offset + PEM_START.len(),
+        }
+    }
+    ret
+}
+
+#[cfg(test)]
+#[test]
+fn test_scan_blocks() {
+    let blocks = |input: &[u8]| scan_blocks(input).into_iter().map(|b| (b.offset, b.block.block_type.clone().into_bytes(), b.block.data.clone())).collect::<Vec<_>>();
+    let x = |offset| (offset, b"X".to_vec(), b"Man".to_vec());
+
+    assert_eq!(vec![x(4)], blocks(b"log -----BEGIN X-----\nTWFu\n-----END X----- done"));
+    assert_eq!(vec![x(6)], blocks(b"<pre>\n-----BEGIN X-----\nTWFu\n-----END X-----</pre>"));
+    assert_eq!(vec![x(9)], blocks(b"Quote:\n> -----BEGIN X-----\n> TWFu\n> -----END X-----\n"));
+    assert_eq!(vec![x(13)], blocks(b"pem = (\n    \"-----BEGIN X-----\\n\"\n    \"TWFu\\n\"\n    \"-----END X-----\\n\"\n)"));
+    assert_eq!(vec![x(9)], blocks(b"{\"pem\": \"-----BEGIN X-----\\nTWFu\\n-----END X-----\\n\"}"));
+    assert_eq!(vec![x(21)], blocks(b"-----BEGIN X----- no -----BEGIN X-----\nTWFu\n-----END X-----"));
+    assert_eq!(vec![x(0), x(59)], blocks(b"-----BEGIN X-----\nTWFu\n-----END X-----\n\n-----BEGIN Y-----\n\n-----BEGIN X-----\nTWFu\n-----END X-----"));
+    assert!(blocks(b"-----BEGIN X-----").is_empty());
+
+    // `+` ending a line of base64 is data, not string concatenation
+    let plus = |offset| (offset, b"X".to_vec(), b"Ma~".to_vec());
+    assert_eq!(vec![plus(2)], blocks(b"> -----BEGIN X-----\n> TWF+\n> -----END X-----\n"));
+    assert_eq!(vec![plus(14)], blocks(b"String pem = \"-----BEGIN X-----\\n\" +\n    \"TWF+\\n\" +\n    \"-----END X-----\\n\";"));
+    assert_eq!(vec![plus(14)], blocks(b"String pem = \"-----BEGIN X-----\\n\" +\n             \"TWF+\\n\" +\n             \"-----END X-----\\n\";"));
+}
@@ -0,0 +1,206 @@
+//! serde support, blocks are serialized as their PEM encoding, ending with a line ending.
+//!
+//! Fields holding PEM strings are decoded during deserialization with the helper modules:
+//!
+//!```ignore
+//! #[macro_use]
+//! extern crate nom_pem;
+//!
+//! // `#[serde(with = "certificate")]` for DER bytes in `CERTIFICATE` blocks
+//! serde_der!(certificate, "CERTIFICATE");
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Config {
+//!     /// DER bytes of a block with any label
+//!     #[serde(with = "nom_pem::serde::der")]
+//!     ca: Vec<u8>,
+//!     #[serde(with = "certificate")]
+//!     client: Vec<u8>,
+//!     /// a type implementing `FromPem` and `ToPem`, e.g. a newtype of the DER bytes
+//!     #[serde(with = "nom_pem::serde::typed")]
+//!     server: Certificate,
+//!     #[serde(with = "nom_pem::serde::bundle")]
+//!     chain: Vec<OwnedBlock>,
+//! }
+//! ```
+
+#[cfg(not(feature = "std"))]
+use alloc::string::String;
+use serde_crate::de::Error;
+use serde_crate::{Deserialize, Deserializer, Serialize, Serializer};
+#[cfg(feature = "zeroize")]
+use zeroize::Zeroize;
+use super::{decode_block, Block, OwnedBlock, PemParsingError};
+
+impl<'a> Serialize for Block<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl Serialize for OwnedBlock {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for OwnedBlock {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<OwnedBlock, D::Error> {
+        with_pem(deserializer, |pem| decode_block(pem.as_bytes()).map(|block| OwnedBlock::from(&block)))
+    }
+}
+
+/// passes the deserialized PEM string to `f`, which may hold key material and is wiped afterwards,
+/// the line ending after the last END line is optional as in the serialized form it is always written
+fn with_pem<'de, D, T, F>(deserializer: D, f: F) -> Result<T, D::Error>
+    where D: Deserializer<'de>, F: FnOnce(&str) -> Result<T, PemParsingError> {
+    let mut pem = String::deserialize(deserializer)?;
+    // decode_blocks needs the line ending after the last END line
+    if !pem.ends_with('\n') {
+        pem.push('\n');
+    }
+    let result = f(&pem);
+    #[cfg(feature = "zeroize")]
+    pem.zeroize();
+    result.map_err(D::Error::custom)
+}
+
+/// `#[serde(with = "nom_pem::serde::typed")]` for fields whose type implements `FromPem` and `ToPem`
+pub mod typed {
+    use serde_crate::{Deserializer, Serializer};
+    use super::super::{FromPem, ToPem};
+
+    pub fn serialize<T: ToPem, S: Serializer>(value: &T, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(&value.to_block())
+    }
+
+    /// parses the first block of the PEM string, with an error if its label is not one of `T::LABELS`
+    pub fn deserialize<'de, T: FromPem, D: Deserializer<'de>>(deserializer: D) -> Result<T, D::Error> {
+        super::with_pem(deserializer, |pem| T::from_pem(pem))
+    }
+}
+
+/// `#[serde(with = "nom_pem::serde::der")]` for DER bytes in `Vec<u8>` fields, read from the first
+/// block of the PEM string whatever its label and written as a block labeled `DEFAULT_LABEL`,
+/// `serde_der!` defines a module checking and writing another label
+pub mod der {
+    #[cfg(not(feature = "std"))]
+    use core::{fmt, mem};
+    #[cfg(feature = "std")]
+    use std::{fmt, mem};
+
+    #[cfg(not(feature = "std"))]
+    use alloc::{string::String, vec::Vec};
+    use serde_crate::{Deserializer, Serializer};
+    use super::super::{decode_block, PemParsingError};
+    use super::super::display::write_base64;
+
+    /// the label written by `serialize`
+    pub const DEFAULT_LABEL: &str = "CERTIFICATE";
+
+    pub fn serialize<S: Serializer>(der: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_with_label(der, DEFAULT_LABEL, serializer)
+    }
+
+    /// the data of the first block of the PEM string, which may carry any label
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        super::with_pem(deserializer, |pem| {
+            let mut block = decode_block(pem.as_bytes())?;
+            Ok(mem::take(&mut block.data))
+        })
+    }
+
+    /// a block without headers, written like `Block` without copying the data
+    struct DerBlock<'b>(&'b str, &'b [u8]);
+
+    impl<'b> fmt::Display for DerBlock<'b> {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            writeln!(f, "-----BEGIN {}-----", self.0)?;
+            write_base64(f, self.1, 64)?;
+            writeln!(f, "-----END {}-----", self.0)
+        }
+    }
+
+    /// serializes `der` as a block with the given label
+    pub fn serialize_with_label<S: Serializer>(der: &[u8], label: &str, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(&DerBlock(label, der))
+    }
+
+    /// the data of the first block of the PEM string, with an error if its label is not one of `labels`
+    pub fn deserialize_with_label<'de, D: Deserializer<'de>>(deserializer: D, labels: &[&str]) -> Result<Vec<u8>, D::Error> {
+        super::with_pem(deserializer, |pem| {
+            let mut block = decode_block(pem.as_bytes())?;
+            if !labels.contains(&block.block_type) {
+                return Err(PemParsingError::UnexpectedBlockType(String::from(block.block_type)));
+            }
+            Ok(mem::take(&mut block.data))
+        })
+    }
+}
+
+/// Defines a module for `#[serde(with = "...")]` on DER bytes, i.e. fields of a type implementing
+/// `AsRef<[u8]>` and `From<Vec<u8>>` like `Vec<u8>`.
+///
+/// The field is serialized as a block with the first label, and deserialized from the first
+/// block of the PEM string, which has to carry one of the labels.
+///
+/// ```ignore
+/// serde_der!(certificate, "CERTIFICATE", "X509 CERTIFICATE");
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Config {
+///     #[serde(with = "certificate")]
+///     ca: Vec<u8>,
+/// }
+/// ```
+#[macro_export]
+macro_rules! serde_der {
+    ($name:ident, $label:expr $(, $labels:expr)*) => {
+        mod $name {
+            pub fn serialize<T, S>(der: &T, serializer: S) -> $crate::__private::Result<S::Ok, S::Error>
+                where T: AsRef<[u8]>, S: $crate::__private::Serializer {
+                $crate::serde::der::serialize_with_label(der.as_ref(), $label, serializer)
+            }
+
+            pub fn deserialize<'de, T, D>(deserializer: D) -> $crate::__private::Result<T, D::Error>
+                where T: From<$crate::__private::Vec<u8>>, D: $crate::__private::Deserializer<'de> {
+                $crate::serde::der::deserialize_with_label(deserializer, &[$label $(, $labels)*]).map(T::from)
+            }
+        }
+    };
+}
+
+/// `#[serde(with = "nom_pem::serde::bundle")]` for `Vec<OwnedBlock>` fields holding all blocks of a PEM string
+pub mod bundle {
+    #[cfg(not(feature = "std"))]
+    use core::fmt;
+    #[cfg(feature = "std")]
+    use std::fmt;
+
+    #[cfg(not(feature = "std"))]
+    use alloc::vec::Vec;
+    use serde_crate::{Deserializer, Serializer};
+    use super::super::{decode_blocks, OwnedBlock};
+
+    struct Bundle<'b>(&'b [OwnedBlock]);
+
+    impl<'b> fmt::Display for Bundle<'b> {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            for block in self.0 {
+                write!(f, "{}", block)?;
+            }
+            Ok(())
+        }
+    }
+
+    pub fn serialize<S: Serializer>(blocks: &[OwnedBlock], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(&Bundle(blocks))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<OwnedBlock>, D::Error> {
+        super::with_pem(deserializer, |pem| {
+            let blocks = decode_blocks(pem.as_bytes())?;
+            Ok(blocks.iter().map(OwnedBlock::from).collect())
+        })
+    }
+}
@@ -0,0 +1,105 @@
+//! Positions of the parts of parsed blocks in their input, for pointing at them in
+//! error reports and editors.
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+use super::{nom_result, Block, PemParsingError};
+use super::parsers::pem_blocks_with_spans;
+
+/// a byte range of the input and the line of its start, counted from 1
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+}
+
+/// the positions of the parts of a block, see `decode_blocks_with_spans`
+#[derive(Debug, PartialEq, Clone)]
+pub struct BlockSpans {
+    /// from the BEGIN line to the END line
+    pub block: Span,
+    /// the BEGIN line without its line ending
+    pub begin: Span,
+    /// one per entry of `Block::headers`, from the name to the end of the value
+    /// including continuation lines
+    pub headers: Vec<Span>,
+    /// the encoded data, or the clear text of MIC-CLEAR messages, up to the END marker
+    pub body: Span,
+    /// the END marker up to its closing dashes
+    pub end: Span,
+}
+
+/// counts the lines up to increasing offsets
+pub struct Lines<'a> {
+    input: &'a [u8],
+    pos: usize,
+    line: usize,
+}
+
+impl<'a> Lines<'a> {
+    pub fn new(input: &'a [u8]) -> Lines<'a> {
+        Lines { input, pos: 0, line: 1 }
+    }
+
+    pub fn input_len(&self) -> usize {
+        self.input.len()
+    }
+
+    pub fn span(&mut self, start: usize, end: usize) -> Span {
+        self.line += self.input[self.pos..start].iter().filter(|&&b| b == b'\n').count();
+        self.pos = start;
+        Span { start, end, line: self.line }
+    }
+}
+
+/// Decodes the blocks like `decode_blocks` and records the positions of their parts
+/// in `input` in `Block::spans`.
+///
+/// ```
+/// let b = b"-----BEGIN MESSAGE-----
+/// Comment: hello
+///
+/// aGVsbG8gd29ybGQ=
+/// -----END MESSAGE-----
+/// ";
+/// let blocks = nom_pem::decode_blocks_with_spans(b).unwrap();
+/// let spans = blocks[0].spans.as_ref().unwrap();
+/// assert_eq!(b"hello world", &blocks[0].data[..]);
+/// assert_eq!(2, spans.headers[0].line);
+/// assert_eq!(&b"Comment: hello"[..], &b[spans.headers[0].start..spans.headers[0].end]);
+/// assert_eq!(&b"aGVsbG8gd29ybGQ=\n"[..], &b[spans.body.start..spans.body.end]);
+/// ```
+pub fn decode_blocks_with_spans(input: &[u8]) -> Result<Vec<Block<'_>>, PemParsingError> {
+    nom_result(pem_blocks_with_spans(input))
+}
+
+#[cfg(test)]
+#[test]
+fn test_block_spans() {
+    let input = b"-----BEGIN X-----\r\nTWFu\r\n-----END X-----\n-----BEGIN Y-----\nA: 1,\n 2\nB: 3\n\nTWE=\n-----END Y-----  \n";
+    let blocks = decode_blocks_with_spans(input).unwrap();
+    let text = |span: Span| &input[span.start..span.end];
+
+    let spans = blocks[0].spans.as_ref().unwrap();
+    assert_eq!(Span { start: 0, end: 40, line: 1 }, spans.block);
+    assert_eq!(&b"-----BEGIN X-----"[..], text(spans.begin));
+    assert!(spans.headers.is_empty());
+    assert_eq!(&b"TWFu\r\n"[..], text(spans.body));
+    assert_eq!(Span { start: 25, end: 40, line: 3 }, spans.end);
+
+    let spans = blocks[1].spans.as_ref().unwrap();
+    assert_eq!(4, spans.begin.line);
+    assert_eq!(vec![&b"A: 1,\n 2"[..], b"B: 3"], spans.headers.iter().map(|&s| text(s)).collect::<Vec<_>>());
+    assert_eq!(vec![5, 7], spans.headers.iter().map(|s| s.line).collect::<Vec<_>>());
+    assert_eq!((&b"TWE=\n"[..], 9), (text(spans.body), spans.body.line));
+    assert_eq!((&b"-----END Y-----"[..], 10), (text(spans.end), spans.end.line));
+
+    // the END marker does not need to start its line
+    let indented = &b"-----BEGIN X-----\nTWFu\n  -----END X-----\n"[..];
+    let spans = decode_blocks_with_spans(indented).unwrap()[0].spans.clone().unwrap();
+    assert_eq!((&b"TWFu\n  "[..], &b"-----END X-----"[..], 3), (&indented[spans.body.start..spans.body.end], &indented[spans.end.start..spans.end.end], spans.end.line));
+    let inline = &b"-----BEGIN X-----\nTWFu -----END X-----\n"[..];
+    let spans = decode_blocks_with_spans(inline).unwrap()[0].spans.clone().unwrap();
+    assert_eq!((&b"TWFu "[..], &b"-----END X-----"[..], 2), (&inline[spans.body.start..spans.body.end], &inline[spans.end.start..spans.end.end], spans.end.line));
+}
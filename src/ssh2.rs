@@ -0,0 +1,183 @@
+//! SSH2 public key file format, see RFC 4716.
+//!
+//! The key blob of a `---- BEGIN SSH2 PUBLIC KEY ----` file becomes the data of a
+//! `Block`, its headers become `HeaderEntry::Entry` values with one unquoted value each.
+
+#[cfg(not(feature = "std"))]
+use core::{fmt, str};
+#[cfg(feature = "std")]
+use std::{fmt, str};
+
+#[cfg(not(feature = "std"))]
+use alloc::{string::String, vec::Vec};
+use nom::{IResult, Err, ErrorKind, Needed, line_ending};
+use super::{Block, BodyEncoding, HeaderEntry, PemParsingError, base64, nom_result};
+use super::parsers::{spaces, cleanup_spaces};
+use super::display::write_base64;
+
+static SSH2_START: &[u8] = b"---- BEGIN ";
+static SSH2_END: &[u8] = b"---- END ";
+
+/// maximum length of a line excluding the line ending, see RFC 4716 section 3
+pub const SSH2_LINE_LENGTH: usize = 72;
+
+/// base64 characters per line, as written by ssh-keygen
+const SSH2_BASE64_WIDTH: usize = 70;
+
+fn ssh2_dashed_string(i: &[u8]) -> IResult<&[u8], &str> {
+    for pos in 0..i.len() {
+        let b = i[pos];
+        if b == 13 || b == 10 { return Err(Err::Error(error_position!(i, ErrorKind::Custom(0xbb0101)))); }
+        if i[pos..].starts_with(b" ----") {
+            return match str::from_utf8(&i[..pos]) {
+                Ok(s) => Ok((&i[(pos + 5)..], s)),
+                Err(_) => Err(Err::Error(error_position!(i, ErrorKind::Custom(0xbb0102))))
+            };
+        }
+    }
+    Err(Err::Incomplete(Needed::Size(5)))
+}
+
+named!(pub ssh2_begin<&str>, do_parse!(
+        tag!(SSH2_START) >>
+        s: ssh2_dashed_string >>
+        spaces >>
+        line_ending >>
+        (s)
+    ));
+
+named!(pub ssh2_footer<&str>, do_parse!(
+    tag!(SSH2_END) >>
+    s: ssh2_dashed_string >>
+    cleanup_spaces >>
+    (s)
+));
+
+#[cfg(test)]
+#[test]
+fn test_ssh2_begin() {
+    assert_eq!(Ok((&[88][..], "SSH2 PUBLIC KEY")), ssh2_begin(b"---- BEGIN SSH2 PUBLIC KEY ----
+X"));
+    assert!(ssh2_begin(b"---- BEGIN SSH2 PUBLIC KEY ---
+X").is_err());
+    assert!(ssh2_begin(b"-----BEGIN SSH2 PUBLIC KEY-----
+X").is_err());
+}
+
+/// printable US-ASCII except space and colon
+pub fn is_ssh2_header_key_char(c: u8) -> bool { c > 0x20 && c < 0x7f && c != b':' }
+
+named!(ssh2_header_key<&str>, map_res!(take_while1!(is_ssh2_header_key_char), str::from_utf8));
+
+/// reads a header value, joining lines that end with a backslash and removing surrounding quotes
+fn ssh2_header_value(i: &[u8]) -> IResult<&[u8], String> {
+    let mut value = String::new();
+    let mut rest = i;
+    loop {
+        let end = match rest.iter().position(|&b| b == 10) {
+            Some(end) => end,
+            None => return Err(Err::Incomplete(Needed::Size(1))),
+        };
+        let mut line = &rest[..end];
+        if line.last() == Some(&13) {
+            line = &line[..line.len() - 1];
+        }
+        rest = &rest[end + 1..];
+        let continued = line.last() == Some(&b'\\');
+        if continued {
+            line = &line[..line.len() - 1];
+        }
+        match str::from_utf8(line) {
+            Ok(s) => value.push_str(s),
+            Err(_) => return Err(Err::Error(error_position!(i, ErrorKind::Custom(0xbb0103)))),
+        }
+        if !continued {
+            break;
+        }
+    }
+    if value.len() >= 2 && value.starts_with('"') && value.ends_with('"') {
+        value = String::from(&value[1..value.len() - 1]);
+    }
+    Ok((rest, value))
+}
+
+named!(pub ssh2_header<HeaderEntry>, do_parse!(
+    key: ssh2_header_key >>
+    tag!(":") >>
+    spaces >>
+    value: ssh2_header_value >>
+    (HeaderEntry::Entry(key, vec![value]))
+));
+
+#[cfg(test)]
+#[test]
+fn test_ssh2_header() {
+    assert_eq!(Ok((&[88][..], HeaderEntry::Entry("Comment", vec![String::from("a, quoted comment")]))),
+               ssh2_header(b"Comment: \"a, quoted comment\"
+X"));
+    assert_eq!(Ok((&[88][..], HeaderEntry::Entry("x-command", vec![String::from("/bin/sh -c true")]))),
+               ssh2_header(b"x-command: /bin/sh \\
+-c true
+X"));
+    assert!(ssh2_header(b"AAAAC3NzaC1lZDI1NTE5AAAAIL9ORbg1NP9gf45R914xcS5Yd6HtbTgYvmArX+uQcb1j
+X").is_err());
+}
+
+named!(pub ssh2_public_key<Block>, do_parse!(
+    block_type: ssh2_begin >>
+    headers: fold_many0!(ssh2_header, Vec::new(), |mut headers: Vec<_>, header| { headers.push(header); headers }) >>
+    data : ws!(base64::base64) >>
+    ssh2_footer >>
+    (Block{block_type, headers, data, encoding: BodyEncoding::Base64, spans: None})
+));
+
+/// Decodes a RFC 4716 `---- BEGIN SSH2 PUBLIC KEY ----` file.
+pub fn decode_ssh2_public_key<'a>(input: &'a [u8]) -> Result<Block<'a>, PemParsingError> {
+    nom_result(ssh2_public_key(input))
+}
+
+fn write_ssh2_header(f: &mut fmt::Formatter, key: &str, value: &str) -> fmt::Result {
+    // a trailing backslash would continue the line and surrounding quotes would be removed
+    let quote = value.contains(' ') || value.ends_with('\\') || value.starts_with('"');
+    let quoted = if quote { format!("\"{}\"", value) } else { String::from(value) };
+    let line = format!("{}: {}", key, quoted);
+    let mut s: &str = &line;
+    // continued lines end with a backslash, so they hold one byte less
+    while s.len() > SSH2_LINE_LENGTH {
+        let mut split = SSH2_LINE_LENGTH - 1;
+        while !s.is_char_boundary(split) {
+            split -= 1;
+        }
+        let (head, tail) = s.split_at(split);
+        writeln!(f, "{}\\", head)?;
+        s = tail;
+    }
+    writeln!(f, "{}", s)
+}
+
+/// Encodes a block in the RFC 4716 format.
+///
+/// ```
+/// let b = b"---- BEGIN SSH2 PUBLIC KEY ----
+/// Comment: \"256-bit ED25519, converted by root@vm from OpenSSH\"
+/// AAAAC3NzaC1lZDI1NTE5AAAAIL9ORbg1NP9gf45R914xcS5Yd6HtbTgYvmArX+uQcb1j
+/// ---- END SSH2 PUBLIC KEY ----
+/// ";
+/// let block = nom_pem::decode_ssh2_public_key(b).unwrap();
+/// assert_eq!(&b[..], format!("{}", nom_pem::Ssh2PublicKey(&block)).as_bytes());
+/// ```
+pub struct Ssh2PublicKey<'b, 'a: 'b>(pub &'b Block<'a>);
+
+impl<'b, 'a> fmt::Display for Ssh2PublicKey<'b, 'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "---- BEGIN {} ----", &self.0.block_type)?;
+        for header in self.0.headers.iter() {
+            match *header {
+                HeaderEntry::Entry(key, ref values) => write_ssh2_header(f, key, &values.join(","))?,
+                ref other => writeln!(f, "{}", other)?,
+            }
+        }
+        write_base64(f, &self.0.data, SSH2_BASE64_WIDTH)?;
+        writeln!(f, "---- END {} ----", &self.0.block_type)
+    }
+}
@@ -0,0 +1,83 @@
+//! Traits for user types with a PEM form, e.g. wrappers of certificates or keys.
+
+#[cfg(not(feature = "std"))]
+use alloc::{string::String, vec::Vec};
+use super::{decode_block, Block, BodyEncoding, PemParsingError};
+
+/// A type that is read from the DER data of a PEM block.
+///
+/// `nom_pem_macros` derives it for newtypes whose field implements `From<&[u8]>`.
+pub trait FromPem: Sized {
+    /// labels of the blocks that hold the type
+    const LABELS: &'static [&'static str];
+
+    /// parses the data of a block
+    fn from_der(der: &[u8]) -> Result<Self, PemParsingError>;
+
+    /// Parses the data of `block` after checking its label against `LABELS`.
+    fn from_block(block: &Block) -> Result<Self, PemParsingError> {
+        if !Self::LABELS.contains(&block.block_type) {
+            return Err(PemParsingError::UnexpectedBlockType(String::from(block.block_type)));
+        }
+        Self::from_der(&block.data)
+    }
+
+    /// Decodes the first block of `pem` like `decode_block` and parses it.
+    fn from_pem(pem: &str) -> Result<Self, PemParsingError> {
+        Self::from_block(&decode_block(pem.as_bytes())?)
+    }
+}
+
+/// A type that is written as the DER data of a PEM block.
+///
+/// `nom_pem_macros` derives it for newtypes whose field implements `AsRef<[u8]>`.
+pub trait ToPem {
+    /// label of the written block
+    const LABEL: &'static str;
+
+    /// encodes the data of the block
+    fn to_der(&self) -> Vec<u8>;
+
+    /// a block without headers holding `to_der`
+    fn to_block(&self) -> Block<'static> {
+        Block { block_type: Self::LABEL, headers: Vec::new(), data: self.to_der(), encoding: BodyEncoding::Base64, spans: None }
+    }
+
+    /// the PEM encoding of `to_block`
+    fn to_pem(&self) -> String {
+        format!("{}", self.to_block())
+    }
+}
+
+#[cfg(test)]
+struct Der(Vec<u8>);
+
+#[cfg(test)]
+impl FromPem for Der {
+    const LABELS: &'static [&'static str] = &["A", "B"];
+
+    fn from_der(der: &[u8]) -> Result<Der, PemParsingError> {
+        Ok(Der(der.to_vec()))
+    }
+}
+
+#[cfg(test)]
+impl ToPem for Der {
+    const LABEL: &'static str = "A";
+
+    fn to_der(&self) -> Vec<u8> {
+        self.0.clone()
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_pem_traits() {
+    let der = Der::from_pem("-----BEGIN B-----\nTWFu\n-----END B-----\n").unwrap();
+    assert_eq!(b"Man", &der.0[..]);
+    assert_eq!("-----BEGIN A-----\nTWFu\n-----END A-----\n", der.to_pem());
+    match Der::from_pem("-----BEGIN C-----\nTWFu\n-----END C-----\n") {
+        Err(PemParsingError::UnexpectedBlockType(ref label)) => assert_eq!("C", label),
+        _ => panic!("UnexpectedBlockType expected"),
+    }
+}
@@ -0,0 +1,149 @@
+//! Validation of the header order and combination of RFC 1421 messages, see RFC 1421 section 4.6
+//! and RFC 1424 section 5.
+
+#[cfg(not(feature = "std"))]
+use core::fmt;
+#[cfg(feature = "std")]
+use std::fmt;
+
+#[cfg(not(feature = "std"))]
+use alloc::{string::String, vec::Vec};
+use super::{Block, HeaderEntry, ProcTypeType};
+
+/// a violation of the RFC 1421 header rules, positions are indices into `Block::headers`
+#[derive(Debug, PartialEq, Clone)]
+pub enum HeaderViolation {
+    MissingProcType,
+    /// `Proc-Type` has to be the first header
+    ProcTypeNotFirst(usize),
+    /// the `Proc-Type` version is not 4
+    UnsupportedVersion(u8),
+    /// a header that may only appear once, with its name
+    DuplicateHeader(usize, String),
+    MissingContentDomain,
+    /// `Content-Domain` has to follow `Proc-Type`
+    ContentDomainMisplaced(usize),
+    /// `ENCRYPTED` messages need a `DEK-Info`
+    MissingDEKInfo,
+    /// `DEK-Info` has to follow `Content-Domain`
+    DEKInfoMisplaced(usize),
+    /// `DEK-Info` in a message that is not `ENCRYPTED`
+    UnexpectedDEKInfo(usize),
+    /// no `Originator-ID-*` or `Originator-Certificate`
+    MissingOriginator,
+    /// `ENCRYPTED` messages need at least one `Recipient-ID-*`
+    MissingRecipient,
+    /// a `Recipient-ID-*` not followed by its `Key-Info`
+    RecipientWithoutKeyInfo(usize),
+    /// a `Key-Info` that follows neither an originator nor a recipient
+    OrphanKeyInfo(usize),
+    /// `CRL` messages need at least one `CRL` header
+    MissingCRL,
+}
+
+impl fmt::Display for HeaderViolation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            HeaderViolation::MissingProcType => write!(f, "Proc-Type is missing"),
+            HeaderViolation::ProcTypeNotFirst(i) => write!(f, "Proc-Type at position {} is not the first header", i),
+            HeaderViolation::UnsupportedVersion(v) => write!(f, "Proc-Type version {} is not supported", v),
+            HeaderViolation::DuplicateHeader(i, ref name) => write!(f, "{} at position {} is repeated", name, i),
+            HeaderViolation::MissingContentDomain => write!(f, "Content-Domain is missing"),
+            HeaderViolation::ContentDomainMisplaced(i) => write!(f, "Content-Domain at position {} does not follow Proc-Type", i),
+            HeaderViolation::MissingDEKInfo => write!(f, "DEK-Info is missing"),
+            HeaderViolation::DEKInfoMisplaced(i) => write!(f, "DEK-Info at position {} does not follow Content-Domain", i),
+            HeaderViolation::UnexpectedDEKInfo(i) => write!(f, "DEK-Info at position {} in a message that is not encrypted", i),
+            HeaderViolation::MissingOriginator => write!(f, "originator is missing"),
+            HeaderViolation::MissingRecipient => write!(f, "recipient is missing"),
+            HeaderViolation::RecipientWithoutKeyInfo(i) => write!(f, "recipient at position {} is not followed by Key-Info", i),
+            HeaderViolation::OrphanKeyInfo(i) => write!(f, "Key-Info at position {} follows neither originator nor recipient", i),
+            HeaderViolation::MissingCRL => write!(f, "CRL is missing"),
+        }
+    }
+}
+
+fn is_originator(header: &HeaderEntry) -> bool {
+    matches!(*header, HeaderEntry::OriginatorIDSymmetric(_) | HeaderEntry::OriginatorIDAsymmetric(_) | HeaderEntry::OriginatorCertificate(_))
+}
+
+fn is_recipient(header: &HeaderEntry) -> bool {
+    matches!(*header, HeaderEntry::RecipientIDSymmetric(_) | HeaderEntry::RecipientIDAsymmetric(_))
+}
+
+impl<'a> Block<'a> {
+    /// Checks the headers against the rules of RFC 1421 section 4.6,
+    /// an empty list means the headers are valid.
+    pub fn validate_headers(&self) -> Vec<HeaderViolation> {
+        let headers = &self.headers;
+        let mut violations = Vec::new();
+        let position = |f: &dyn Fn(&HeaderEntry) -> bool| headers.iter().position(f);
+
+        let proc_type = match position(&|h| matches!(*h, HeaderEntry::ProcType(..))) {
+            Some(i) => i,
+            None => return vec![HeaderViolation::MissingProcType],
+        };
+        if proc_type != 0 {
+            violations.push(HeaderViolation::ProcTypeNotFirst(proc_type));
+        }
+        let proc_type = match headers[proc_type] {
+            HeaderEntry::ProcType(version, ref t) => {
+                if version != 4 {
+                    violations.push(HeaderViolation::UnsupportedVersion(version));
+                }
+                t
+            }
+            _ => unreachable!(),
+        };
+
+        // Proc-Type, Content-Domain and DEK-Info may only appear once
+        let mut seen = [false; 3];
+        for (i, header) in headers.iter().enumerate() {
+            let (once, name) = match *header {
+                HeaderEntry::ProcType(..) => (0, "Proc-Type"),
+                HeaderEntry::ContentDomain(_) => (1, "Content-Domain"),
+                HeaderEntry::DEKInfo(..) => (2, "DEK-Info"),
+                _ => continue,
+            };
+            if seen[once] {
+                violations.push(HeaderViolation::DuplicateHeader(i, String::from(name)));
+            }
+            seen[once] = true;
+        }
+
+        if *proc_type == ProcTypeType::CRL {
+            if position(&|h| matches!(*h, HeaderEntry::CRL(_))).is_none() {
+                violations.push(HeaderViolation::MissingCRL);
+            }
+            return violations;
+        }
+
+        match position(&|h| matches!(*h, HeaderEntry::ContentDomain(_))) {
+            None => violations.push(HeaderViolation::MissingContentDomain),
+            Some(1) => {}
+            Some(i) => violations.push(HeaderViolation::ContentDomainMisplaced(i)),
+        }
+        match (position(&|h| matches!(*h, HeaderEntry::DEKInfo(..))), *proc_type == ProcTypeType::ENCRYPTED) {
+            (None, true) => violations.push(HeaderViolation::MissingDEKInfo),
+            (Some(2), true) | (None, false) => {}
+            (Some(i), true) => violations.push(HeaderViolation::DEKInfoMisplaced(i)),
+            (Some(i), false) => violations.push(HeaderViolation::UnexpectedDEKInfo(i)),
+        }
+        if position(&is_originator).is_none() {
+            violations.push(HeaderViolation::MissingOriginator);
+        }
+        if *proc_type == ProcTypeType::ENCRYPTED && position(&is_recipient).is_none() {
+            violations.push(HeaderViolation::MissingRecipient);
+        }
+
+        for (i, header) in headers.iter().enumerate() {
+            if is_recipient(header) && !matches!(headers.get(i + 1), Some(&HeaderEntry::KeyInfo(_))) {
+                violations.push(HeaderViolation::RecipientWithoutKeyInfo(i));
+            }
+            let after_id = i > 0 && (is_recipient(&headers[i - 1]) || is_originator(&headers[i - 1]));
+            if matches!(*header, HeaderEntry::KeyInfo(_)) && !after_id {
+                violations.push(HeaderViolation::OrphanKeyInfo(i));
+            }
+        }
+        violations
+    }
+}
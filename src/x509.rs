@@ -0,0 +1,318 @@
+//! Read-only view of X.509 certificates (RFC 5280) carried in CERTIFICATE blocks.
+
+#[cfg(not(feature = "std"))]
+use core::fmt;
+#[cfg(feature = "std")]
+use std::fmt;
+
+#[cfg(not(feature = "std"))]
+use alloc::{string::String, vec::Vec};
+use super::{Block, PemParsingError};
+use super::der::*;
+
+/// labels used for PEM encoded certificates
+pub static CERTIFICATE_LABELS: &[&str] = &["CERTIFICATE", "X509 CERTIFICATE", "TRUSTED CERTIFICATE"];
+
+/// UTCTime or GeneralizedTime of a certificate, always UTC
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub struct Asn1Time {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+}
+
+impl Asn1Time {
+    /// seconds since 1970-01-01T00:00:00Z
+    pub fn unix_timestamp(&self) -> i64 {
+        // days from civil, see http://howardhinnant.github.io/date_algorithms.html
+        let y = self.year as i64 - if self.month <= 2 { 1 } else { 0 };
+        let era = if y >= 0 { y } else { y - 399 } / 400;
+        let yoe = y - era * 400;
+        let m = self.month as i64;
+        let doy = (153 * (if m > 2 { m - 3 } else { m + 9 }) + 2) / 5 + self.day as i64 - 1;
+        let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+        let days = era * 146097 + doe - 719468;
+        days * 86400 + self.hour as i64 * 3600 + self.minute as i64 * 60 + self.second as i64
+    }
+}
+
+impl fmt::Display for Asn1Time {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z", self.year, self.month, self.day, self.hour, self.minute, self.second)
+    }
+}
+
+/// one entry of the subjectAltName extension
+#[derive(Debug, PartialEq, Clone)]
+pub enum AltName {
+    Dns(String),
+    Email(String),
+    Uri(String),
+    IpAddress(Vec<u8>),
+    /// any other GeneralName, identified by its context tag number
+    Other(u8),
+}
+
+impl fmt::Display for AltName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            AltName::Dns(ref s) => write!(f, "DNS:{}", s),
+            AltName::Email(ref s) => write!(f, "email:{}", s),
+            AltName::Uri(ref s) => write!(f, "URI:{}", s),
+            AltName::IpAddress(ref ip) if ip.len() == 4 => write!(f, "IP Address:{}.{}.{}.{}", ip[0], ip[1], ip[2], ip[3]),
+            AltName::IpAddress(ref ip) => {
+                write!(f, "IP Address:")?;
+                for (i, pair) in ip.chunks(2).enumerate() {
+                    if i > 0 {
+                        write!(f, ":")?;
+                    }
+                    let word = pair.iter().fold(0u16, |a, &b| (a << 8) | b as u16);
+                    write!(f, "{:X}", word)?;
+                }
+                Ok(())
+            }
+            AltName::Other(n) => write!(f, "othername:<{}>", n),
+        }
+    }
+}
+
+/// the fields of a certificate needed for inventory and expiry checks
+#[derive(Debug, PartialEq, Clone)]
+pub struct CertificateSummary {
+    /// serial number as big endian bytes
+    pub serial: Vec<u8>,
+    pub subject: String,
+    pub issuer: String,
+    pub not_before: Asn1Time,
+    pub not_after: Asn1Time,
+    /// algorithm of the SubjectPublicKeyInfo, e.g. `rsaEncryption`
+    pub public_key_algorithm: String,
+    pub subject_alt_names: Vec<AltName>,
+    /// CA flag of the basicConstraints extension, `None` if the extension is absent
+    pub ca: Option<bool>,
+}
+
+/// the parts of a DER encoded certificate, borrowed from the block data
+#[derive(Debug)]
+pub struct Certificate<'a> {
+    pub serial: &'a [u8],
+    pub issuer: &'a [u8],
+    pub validity: (Asn1Time, Asn1Time),
+    pub subject: &'a [u8],
+    pub subject_public_key_info: &'a [u8],
+    pub extensions: Vec<(&'a [u8], bool, &'a [u8])>,
+}
+
+impl<'a> Certificate<'a> {
+    pub fn extension(&self, oid: &[u8]) -> Option<&'a [u8]> {
+        self.extensions.iter().find(|e| e.0 == oid).map(|e| e.2)
+    }
+
+    /// keyIdentifier of the subjectKeyIdentifier extension
+    pub fn subject_key_id(&self) -> Result<Option<&'a [u8]>, PemParsingError> {
+        match self.extension(OID_SUBJECT_KEY_IDENTIFIER) {
+            Some(value) => DerReader::new(value).read(TAG_OCTET_STRING).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// keyIdentifier of the authorityKeyIdentifier extension
+    pub fn authority_key_id(&self) -> Result<Option<&'a [u8]>, PemParsingError> {
+        match self.extension(OID_AUTHORITY_KEY_IDENTIFIER) {
+            Some(value) => DerReader::new(value).read_sequence()?.read_optional(tag_implicit(0)),
+            None => Ok(None),
+        }
+    }
+}
+
+pub fn parse_certificate(data: &[u8]) -> Result<Certificate<'_>, PemParsingError> {
+    let mut cert = DerReader::new(data).read_sequence()?;
+    let mut tbs = cert.read_sequence()?;
+    tbs.read_optional(tag_explicit(0))?;
+    let serial = tbs.read(TAG_INTEGER)?;
+    tbs.read(TAG_SEQUENCE)?; // signature algorithm
+    let issuer = tbs.read(TAG_SEQUENCE)?;
+    let mut validity = tbs.read_sequence()?;
+    let not_before = parse_time(&mut validity)?;
+    let not_after = parse_time(&mut validity)?;
+    let subject = tbs.read(TAG_SEQUENCE)?;
+    let subject_public_key_info = tbs.read_raw()?;
+    tbs.read_optional(tag_implicit(1))?; // issuerUniqueID
+    tbs.read_optional(tag_implicit(2))?; // subjectUniqueID
+    let mut extensions = Vec::new();
+    if let Some(explicit) = tbs.read_optional(tag_explicit(3))? {
+        let mut list = DerReader::new(explicit).read_sequence()?;
+        while !list.is_empty() {
+            let mut extension = list.read_sequence()?;
+            let oid = extension.read(TAG_OID)?;
+            let critical = match extension.read_optional(TAG_BOOLEAN)? {
+                Some(b) => b.first().map(|&b| b != 0).unwrap_or(false),
+                None => false,
+            };
+            let value = extension.read(TAG_OCTET_STRING)?;
+            extensions.push((oid, critical, value));
+        }
+    }
+    Ok(Certificate { serial, issuer, validity: (not_before, not_after), subject, subject_public_key_info, extensions })
+}
+
+fn parse_time(reader: &mut DerReader) -> Result<Asn1Time, PemParsingError> {
+    let (tag, value) = reader.read_any()?;
+    let (year, rest) = match tag {
+        TAG_UTC_TIME if value.len() >= 2 => {
+            let yy = parse_digits(&value[..2])?;
+            (if yy < 50 { 2000 + yy } else { 1900 + yy }, &value[2..])
+        }
+        TAG_GENERALIZED_TIME if value.len() >= 4 => (parse_digits(&value[..4])?, &value[4..]),
+        _ => return Err(PemParsingError::DerError(String::from("invalid time"))),
+    };
+    if rest.len() < 11 || rest[10] != b'Z' {
+        return Err(PemParsingError::DerError(String::from("invalid time")));
+    }
+    Ok(Asn1Time {
+        year,
+        month: parse_digits(&rest[0..2])? as u8,
+        day: parse_digits(&rest[2..4])? as u8,
+        hour: parse_digits(&rest[4..6])? as u8,
+        minute: parse_digits(&rest[6..8])? as u8,
+        second: parse_digits(&rest[8..10])? as u8,
+    })
+}
+
+fn parse_digits(i: &[u8]) -> Result<u16, PemParsingError> {
+    i.iter().try_fold(0u16, |a, &b| {
+        if b.is_ascii_digit() {
+            Ok(a * 10 + (b - b'0') as u16)
+        } else {
+            Err(PemParsingError::DerError(String::from("invalid time")))
+        }
+    })
+}
+
+/// decodes the DirectoryString and IA5String types used in names
+pub fn der_string(tag: u8, value: &[u8]) -> Option<String> {
+    match tag {
+        TAG_UTF8_STRING | TAG_PRINTABLE_STRING | TAG_IA5_STRING => String::from_utf8(value.to_vec()).ok(),
+        TAG_T61_STRING => Some(value.iter().map(|&b| b as char).collect()),
+        TAG_BMP_STRING => {
+            let units: Vec<u16> = value.chunks(2).map(|c| c.iter().fold(0u16, |a, &b| (a << 8) | b as u16)).collect();
+            String::from_utf16(&units).ok()
+        }
+        TAG_UNIVERSAL_STRING => value
+            .chunks(4)
+            .map(|c| char::from_u32(c.iter().fold(0u32, |a, &b| (a << 8) | b as u32)))
+            .collect(),
+        _ => None,
+    }
+}
+
+/// formats the content of a Name as `C=DE, O=Example, CN=example.com`
+pub fn format_name(name: &[u8]) -> Result<String, PemParsingError> {
+    let mut ret = String::new();
+    let mut rdns = DerReader::new(name);
+    while !rdns.is_empty() {
+        let mut rdn = DerReader::new(rdns.read(TAG_SET)?);
+        let mut first = true;
+        while !rdn.is_empty() {
+            let mut attribute = rdn.read_sequence()?;
+            let oid = attribute.read(TAG_OID)?;
+            let (tag, value) = attribute.read_any()?;
+            if !first {
+                ret.push('+');
+            } else if !ret.is_empty() {
+                ret.push_str(", ");
+            }
+            first = false;
+            ret.push_str(&oid_name(oid));
+            ret.push('=');
+            match der_string(tag, value) {
+                Some(s) => ret.push_str(&s),
+                None => {
+                    ret.push('#');
+                    for b in value.iter() {
+                        ret.push_str(&format!("{:02X}", b));
+                    }
+                }
+            }
+        }
+    }
+    Ok(ret)
+}
+
+fn parse_alt_names(value: &[u8]) -> Result<Vec<AltName>, PemParsingError> {
+    let mut ret = Vec::new();
+    let mut names = DerReader::new(value).read_sequence()?;
+    while !names.is_empty() {
+        let (tag, value) = names.read_any()?;
+        let n = tag & 0x1f;
+        let text = || String::from_utf8(value.to_vec()).map_err(|_| PemParsingError::DerError(String::from("invalid IA5String")));
+        ret.push(match n {
+            1 => AltName::Email(text()?),
+            2 => AltName::Dns(text()?),
+            6 => AltName::Uri(text()?),
+            7 => AltName::IpAddress(value.to_vec()),
+            _ => AltName::Other(n),
+        });
+    }
+    Ok(ret)
+}
+
+fn parse_basic_constraints(value: &[u8]) -> Result<bool, PemParsingError> {
+    let mut constraints = DerReader::new(value).read_sequence()?;
+    Ok(match constraints.read_optional(TAG_BOOLEAN)? {
+        Some(b) => b.first().map(|&b| b != 0).unwrap_or(false),
+        None => false,
+    })
+}
+
+/// algorithm OID of a SubjectPublicKeyInfo
+pub fn spki_algorithm(spki: &[u8]) -> Result<&[u8], PemParsingError> {
+    let mut spki = DerReader::new(spki).read_sequence()?;
+    let mut algorithm = spki.read_sequence()?;
+    algorithm.read(TAG_OID)
+}
+
+impl<'a> Block<'a> {
+    /// Summarizes the certificate of a CERTIFICATE block.
+    pub fn certificate_summary(&self) -> Result<CertificateSummary, PemParsingError> {
+        if !CERTIFICATE_LABELS.contains(&self.block_type) {
+            return Err(PemParsingError::UnexpectedBlockType(String::from(self.block_type)));
+        }
+        let cert = parse_certificate(&self.data)?;
+        let subject_alt_names = match cert.extension(OID_SUBJECT_ALT_NAME) {
+            Some(value) => parse_alt_names(value)?,
+            None => Vec::new(),
+        };
+        let ca = match cert.extension(OID_BASIC_CONSTRAINTS) {
+            Some(value) => Some(parse_basic_constraints(value)?),
+            None => None,
+        };
+        Ok(CertificateSummary {
+            serial: unsigned_integer(cert.serial).to_vec(),
+            subject: format_name(cert.subject)?,
+            issuer: format_name(cert.issuer)?,
+            not_before: cert.validity.0,
+            not_after: cert.validity.1,
+            public_key_algorithm: oid_name(spki_algorithm(cert.subject_public_key_info)?),
+            subject_alt_names,
+            ca,
+        })
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_time() {
+    let mut r = DerReader::new(b"\x17\x0d491231235959Z\x18\x0f20500101000000Z");
+    let t1 = parse_time(&mut r).unwrap();
+    let t2 = parse_time(&mut r).unwrap();
+    assert_eq!("2049-12-31T23:59:59Z", format!("{}", t1));
+    assert_eq!("2050-01-01T00:00:00Z", format!("{}", t2));
+    assert!(t1 < t2);
+    assert_eq!(t1.unix_timestamp() + 1, t2.unix_timestamp());
+    assert_eq!(0, Asn1Time { year: 1970, month: 1, day: 1, hour: 0, minute: 0, second: 0 }.unix_timestamp());
+    assert!(parse_time(&mut DerReader::new(b"\x17\x0b4912312359Z")).is_err());
+}